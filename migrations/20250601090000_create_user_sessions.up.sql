@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS user_sessions (
+    chat_id BIGINT PRIMARY KEY,
+    step TEXT NOT NULL,
+    user_type TEXT NOT NULL,
+    client_id INTEGER NOT NULL DEFAULT -1,
+    photographer_id INTEGER,
+    service_id INTEGER,
+    selected_date DATE,
+    selected_time_start TIME,
+    selected_time_end TIME,
+    agreement BOOLEAN NOT NULL DEFAULT FALSE,
+    client_name TEXT NOT NULL DEFAULT '',
+    client_username TEXT NOT NULL DEFAULT '',
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
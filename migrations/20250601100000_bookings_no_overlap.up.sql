@@ -0,0 +1,10 @@
+-- Запрещаем пересекающиеся активные записи к одному фотографу на уровне БД
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_no_overlap
+    EXCLUDE USING gist (
+        photographer_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled');
@@ -0,0 +1,15 @@
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_no_overlap;
+
+ALTER TABLE bookings
+    ALTER COLUMN status DROP DEFAULT,
+    ALTER COLUMN status TYPE TEXT USING status::text;
+
+DROP TYPE IF EXISTS booking_status;
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_no_overlap
+    EXCLUDE USING gist (
+        photographer_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled');
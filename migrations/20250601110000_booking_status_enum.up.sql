@@ -0,0 +1,26 @@
+CREATE TYPE booking_status AS ENUM ('new', 'confirmed', 'completed', 'cancelled');
+
+-- Ограничение ссылается на status, его нужно пересоздать после смены типа
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_no_overlap;
+
+-- Приводим к единому виду строки, записанные раньше (в том числе по-русски)
+UPDATE bookings SET status = CASE
+    WHEN lower(trim(status)) IN ('new', 'новый', 'новая') THEN 'new'
+    WHEN lower(trim(status)) IN ('confirmed', 'подтвержден', 'подтверждено') THEN 'confirmed'
+    WHEN lower(trim(status)) IN ('completed', 'выполнен', 'выполнено', 'завершен', 'завершено') THEN 'completed'
+    WHEN lower(trim(status)) IN ('cancelled', 'canceled', 'отменен', 'отменено') THEN 'cancelled'
+    -- Неизвестный статус оставляем фотографу на рассмотрение
+    ELSE 'new'
+END;
+
+ALTER TABLE bookings
+    ALTER COLUMN status TYPE booking_status USING status::booking_status,
+    ALTER COLUMN status SET DEFAULT 'new';
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_no_overlap
+    EXCLUDE USING gist (
+        photographer_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled');
@@ -0,0 +1,17 @@
+ALTER TABLE services
+    DROP COLUMN IF EXISTS buffer_before,
+    DROP COLUMN IF EXISTS buffer_after;
+
+ALTER TABLE working_hours
+    ADD COLUMN start_hour INTEGER,
+    ADD COLUMN end_hour INTEGER;
+
+UPDATE working_hours
+SET start_hour = EXTRACT(HOUR FROM start_time)::INTEGER,
+    end_hour = CEIL(EXTRACT(EPOCH FROM end_time) / 3600)::INTEGER;
+
+ALTER TABLE working_hours
+    ALTER COLUMN start_hour SET NOT NULL,
+    ALTER COLUMN end_hour SET NOT NULL,
+    DROP COLUMN start_time,
+    DROP COLUMN end_time;
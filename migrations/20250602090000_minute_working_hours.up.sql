@@ -0,0 +1,19 @@
+-- Рабочие часы с точностью до минуты
+ALTER TABLE working_hours
+    ADD COLUMN start_time TIME,
+    ADD COLUMN end_time TIME;
+
+UPDATE working_hours
+SET start_time = make_time(start_hour, 0, 0),
+    end_time = make_time(end_hour, 0, 0);
+
+ALTER TABLE working_hours
+    ALTER COLUMN start_time SET NOT NULL,
+    ALTER COLUMN end_time SET NOT NULL,
+    DROP COLUMN start_hour,
+    DROP COLUMN end_hour;
+
+-- Подготовка студии перед съёмкой и уборка после, в минутах
+ALTER TABLE services
+    ADD COLUMN buffer_before INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN buffer_after INTEGER NOT NULL DEFAULT 0;
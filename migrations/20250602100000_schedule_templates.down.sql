@@ -0,0 +1,2 @@
+DROP FUNCTION IF EXISTS resolved_working_hours(DATE, DATE);
+DROP TABLE IF EXISTS schedule_templates;
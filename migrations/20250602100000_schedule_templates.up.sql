@@ -0,0 +1,40 @@
+-- Повторяющийся график фотографа
+CREATE TABLE IF NOT EXISTS schedule_templates (
+    id SERIAL PRIMARY KEY,
+    photographer_id INTEGER NOT NULL UNIQUE REFERENCES photographers(id),
+    kind TEXT NOT NULL CHECK (kind IN ('weekly', 'rotation')),
+    -- weekly: рабочие дни недели, 1 = понедельник ... 7 = воскресенье
+    weekdays INTEGER[],
+    -- rotation: days_on рабочих дней, затем days_off выходных, отсчёт от anchor_date
+    days_on INTEGER CHECK (days_on > 0),
+    days_off INTEGER CHECK (days_off >= 0),
+    anchor_date DATE,
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    CHECK (kind <> 'weekly' OR weekdays IS NOT NULL),
+    CHECK (kind <> 'rotation' OR (days_on IS NOT NULL AND days_off IS NOT NULL AND anchor_date IS NOT NULL))
+);
+
+-- Рабочие часы по дням: запись в working_hours на дату перекрывает шаблон.
+-- Запись с start_time >= end_time означает выходной
+CREATE OR REPLACE FUNCTION resolved_working_hours(from_date DATE, to_date DATE)
+RETURNS TABLE (photographer_id INTEGER, date DATE, start_time TIME, end_time TIME) AS $$
+    SELECT p.id,
+           d.day::date,
+           COALESCE(wh.start_time, t.start_time),
+           COALESCE(wh.end_time, t.end_time)
+    FROM photographers p
+    CROSS JOIN generate_series(from_date, to_date, INTERVAL '1 day') AS d(day)
+    LEFT JOIN working_hours wh
+        ON wh.photographer_id = p.id AND wh.date = d.day::date
+    LEFT JOIN schedule_templates t
+        ON t.photographer_id = p.id
+        AND (
+            (t.kind = 'weekly' AND EXTRACT(ISODOW FROM d.day)::INTEGER = ANY(t.weekdays))
+            OR (t.kind = 'rotation'
+                AND (((d.day::date - t.anchor_date) % (t.days_on + t.days_off)) + (t.days_on + t.days_off))
+                    % (t.days_on + t.days_off) < t.days_on)
+        )
+    WHERE wh.id IS NOT NULL OR t.id IS NOT NULL
+$$ LANGUAGE sql STABLE;
@@ -0,0 +1,4 @@
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_room_no_overlap;
+ALTER TABLE bookings DROP COLUMN IF EXISTS room_id;
+ALTER TABLE services DROP COLUMN IF EXISTS room_types;
+DROP TABLE IF EXISTS rooms;
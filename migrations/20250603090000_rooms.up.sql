@@ -0,0 +1,22 @@
+-- Залы студии
+CREATE TABLE IF NOT EXISTS rooms (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    -- Тип зала, например 'hall', 'cyclorama', 'kids'
+    room_type TEXT NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+-- Типы залов, подходящих для услуги. NULL — услуга не занимает зал
+ALTER TABLE services ADD COLUMN room_types TEXT[];
+
+ALTER TABLE bookings ADD COLUMN room_id INTEGER REFERENCES rooms(id);
+
+-- Один зал не может быть занят двумя активными записями одновременно
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_room_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled' AND room_id IS NOT NULL);
@@ -0,0 +1,17 @@
+-- Оплаты записей через Telegram Payments
+CREATE TABLE IF NOT EXISTS payments (
+    id SERIAL PRIMARY KEY,
+    booking_id INTEGER NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    -- Сумма в минимальных единицах валюты (копейках)
+    amount INTEGER NOT NULL CHECK (amount > 0),
+    currency TEXT NOT NULL,
+    -- Процент от стоимости услуги: 100 — полная оплата, меньше — предоплата
+    percent INTEGER NOT NULL CHECK (percent BETWEEN 1 AND 100),
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'paid')),
+    telegram_payment_charge_id TEXT UNIQUE,
+    provider_payment_charge_id TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    paid_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS payments_booking_id_idx ON payments (booking_id);
@@ -0,0 +1,11 @@
+-- Отправленные напоминания: по одной строке на запись и срок напоминания
+CREATE TABLE IF NOT EXISTS booking_reminders (
+    booking_id INTEGER NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    -- За сколько часов до начала съёмки отправлено напоминание
+    offset_hours INTEGER NOT NULL,
+    sent_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (booking_id, offset_hours)
+);
+
+-- Клиент подтвердил по напоминанию, что придёт
+ALTER TABLE bookings ADD COLUMN client_confirmed_at TIMESTAMP WITH TIME ZONE;
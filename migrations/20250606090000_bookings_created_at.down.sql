@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS bookings_new_created_at_idx;
+ALTER TABLE bookings DROP COLUMN IF EXISTS created_at;
@@ -0,0 +1,5 @@
+-- Время создания записи, от него отсчитывается срок подтверждения фотографом.
+-- Существующим записям достаётся время миграции, чтобы они не отменились сразу
+ALTER TABLE bookings ADD COLUMN created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS bookings_new_created_at_idx ON bookings (created_at) WHERE status = 'new';
@@ -0,0 +1,2 @@
+ALTER TABLE user_sessions DROP COLUMN IF EXISTS reschedule_booking_id;
+DROP TABLE IF EXISTS booking_history;
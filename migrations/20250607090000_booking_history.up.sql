@@ -0,0 +1,16 @@
+-- Прежние значения записи при переносе
+CREATE TABLE IF NOT EXISTS booking_history (
+    id SERIAL PRIMARY KEY,
+    booking_id INTEGER NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    booking_start TIMESTAMP NOT NULL,
+    booking_end TIMESTAMP NOT NULL,
+    status booking_status NOT NULL,
+    room_id INTEGER REFERENCES rooms(id),
+    -- Причина изменения, например 'rescheduled'
+    reason TEXT NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS booking_history_booking_id_idx ON booking_history (booking_id);
+
+ALTER TABLE user_sessions ADD COLUMN reschedule_booking_id INTEGER;
@@ -0,0 +1,4 @@
+ALTER TABLE user_sessions DROP COLUMN IF EXISTS edit_target_id;
+ALTER TABLE photographer_services DROP CONSTRAINT IF EXISTS photographer_services_unique;
+ALTER TABLE services DROP COLUMN IF EXISTS is_active;
+ALTER TABLE photographers DROP COLUMN IF EXISTS is_active;
@@ -0,0 +1,16 @@
+-- Деактивированные фотографы и услуги не предлагаются клиентам, но остаются в истории записей
+ALTER TABLE photographers ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE services ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Убираем повторяющиеся связи фотограф-услуга, чтобы администратор мог переключать их
+DELETE FROM photographer_services a
+USING photographer_services b
+WHERE a.id > b.id
+AND a.photographer_id = b.photographer_id
+AND a.service_id = b.service_id;
+
+ALTER TABLE photographer_services
+    ADD CONSTRAINT photographer_services_unique UNIQUE (photographer_id, service_id);
+
+-- Запись, которую редактирует администратор
+ALTER TABLE user_sessions ADD COLUMN edit_target_id INTEGER;
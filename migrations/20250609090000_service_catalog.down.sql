@@ -0,0 +1,2 @@
+ALTER TABLE photographers DROP COLUMN IF EXISTS can_manage_catalog;
+ALTER TABLE services DROP COLUMN IF EXISTS sort_order;
@@ -0,0 +1,6 @@
+-- Порядок услуг в списке для клиентов
+ALTER TABLE services ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
+UPDATE services SET sort_order = id;
+
+-- Фотографы, которым администратор разрешил управлять каталогом услуг
+ALTER TABLE photographers ADD COLUMN can_manage_catalog BOOLEAN NOT NULL DEFAULT FALSE;
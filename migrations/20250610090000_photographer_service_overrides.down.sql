@@ -0,0 +1,3 @@
+ALTER TABLE photographer_services
+    DROP COLUMN IF EXISTS price_override,
+    DROP COLUMN IF EXISTS duration_override;
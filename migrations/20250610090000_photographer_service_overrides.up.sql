@@ -0,0 +1,4 @@
+-- Личные цена и длительность фотографа для услуги; NULL — как в каталоге
+ALTER TABLE photographer_services
+    ADD COLUMN price_override INTEGER CHECK (price_override >= 0),
+    ADD COLUMN duration_override INTEGER CHECK (duration_override > 0);
@@ -0,0 +1,2 @@
+ALTER TABLE bookings DROP COLUMN IF EXISTS price;
+DROP TABLE IF EXISTS price_rules;
@@ -0,0 +1,26 @@
+-- Скидки студии по дням недели и времени суток, например дешевле по утрам в будни
+CREATE TABLE IF NOT EXISTS price_rules (
+    id SERIAL PRIMARY KEY,
+    -- 1 = понедельник ... 7 = воскресенье
+    weekdays INTEGER[] NOT NULL,
+    -- Скидка действует, если съёмка начинается в интервале [start_time, end_time)
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+    percent_off INTEGER NOT NULL CHECK (percent_off BETWEEN 1 AND 100),
+    CHECK (start_time < end_time),
+    CHECK (weekdays <@ ARRAY[1, 2, 3, 4, 5, 6, 7] AND cardinality(weekdays) > 0)
+);
+
+-- Итоговая цена на момент записи; дальнейшие изменения цен её не меняют
+ALTER TABLE bookings ADD COLUMN price INTEGER;
+
+UPDATE bookings b
+SET price = COALESCE(
+    (SELECT ps.price_override FROM photographer_services ps
+     WHERE ps.photographer_id = b.photographer_id AND ps.service_id = b.service_id
+     LIMIT 1),
+    (SELECT s.cost FROM services s WHERE s.id = b.service_id),
+    0
+);
+
+ALTER TABLE bookings ALTER COLUMN price SET NOT NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE user_sessions DROP COLUMN IF EXISTS promo_code_id;
+DROP INDEX IF EXISTS bookings_promo_code_id_idx;
+ALTER TABLE bookings DROP COLUMN IF EXISTS promo_code_id;
+DROP TABLE IF EXISTS promo_codes;
@@ -0,0 +1,24 @@
+-- Промокоды: скидка в процентах или фиксированной суммой
+CREATE TABLE IF NOT EXISTS promo_codes (
+    id SERIAL PRIMARY KEY,
+    -- Хранится в верхнем регистре
+    code VARCHAR(64) NOT NULL UNIQUE,
+    percent_off INTEGER CHECK (percent_off BETWEEN 1 AND 100),
+    amount_off INTEGER CHECK (amount_off > 0),
+    -- Код действует в интервале [valid_from, valid_until), NULL — без ограничения
+    valid_from TIMESTAMP,
+    valid_until TIMESTAMP,
+    -- Сколько неотменённых записей может использовать код, NULL — без ограничения
+    max_uses INTEGER CHECK (max_uses > 0),
+    -- Услуги, на которые действует код, NULL — на все
+    service_ids INTEGER[],
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((percent_off IS NULL) <> (amount_off IS NULL))
+);
+
+ALTER TABLE bookings ADD COLUMN promo_code_id INTEGER REFERENCES promo_codes(id);
+CREATE INDEX IF NOT EXISTS bookings_promo_code_id_idx ON bookings (promo_code_id) WHERE promo_code_id IS NOT NULL;
+
+-- Промокод, введённый клиентом при подтверждении записи
+ALTER TABLE user_sessions ADD COLUMN promo_code_id INTEGER;
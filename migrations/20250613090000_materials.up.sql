@@ -0,0 +1,11 @@
+-- Готовые материалы по завершённым записям
+CREATE TABLE IF NOT EXISTS materials (
+    id SERIAL PRIMARY KEY,
+    booking_id INTEGER NOT NULL REFERENCES bookings(id) ON DELETE CASCADE,
+    -- photo, video, document — file_url содержит file_id Telegram; link — ссылку
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('photo', 'video', 'document', 'link')),
+    file_url TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS materials_booking_id_idx ON materials (booking_id);
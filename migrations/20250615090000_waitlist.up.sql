@@ -0,0 +1,22 @@
+-- Лист ожидания на занятые даты
+CREATE TABLE IF NOT EXISTS waitlist (
+    id SERIAL PRIMARY KEY,
+    client_id INTEGER NOT NULL REFERENCES clients(id),
+    service_id INTEGER NOT NULL REFERENCES services(id),
+    -- NULL — подойдёт любой фотограф
+    photographer_id INTEGER REFERENCES photographers(id),
+    date DATE NOT NULL,
+    -- waiting — в очереди, offered — клиенту предложен слот, booked — записался,
+    -- declined — отказался, expired — не ответил вовремя или дата прошла, cancelled — вышел из очереди
+    status VARCHAR(16) NOT NULL DEFAULT 'waiting'
+        CHECK (status IN ('waiting', 'offered', 'booked', 'declined', 'expired', 'cancelled')),
+    -- Предложенный слот удерживается за клиентом до hold_until
+    offered_photographer_id INTEGER REFERENCES photographers(id),
+    offered_start TIMESTAMP,
+    offered_end TIMESTAMP,
+    hold_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS waitlist_queue_idx ON waitlist (created_at, id) WHERE status = 'waiting';
+CREATE INDEX IF NOT EXISTS waitlist_holds_idx ON waitlist (offered_photographer_id, offered_start) WHERE status = 'offered';
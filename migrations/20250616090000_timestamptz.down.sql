@@ -0,0 +1,31 @@
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_no_overlap;
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_room_no_overlap;
+
+ALTER TABLE waitlist
+    ALTER COLUMN offered_start TYPE TIMESTAMP USING offered_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN offered_end TYPE TIMESTAMP USING offered_end AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN hold_until TYPE TIMESTAMP USING hold_until AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE booking_history
+    ALTER COLUMN booking_start TYPE TIMESTAMP USING booking_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN booking_end TYPE TIMESTAMP USING booking_end AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE bookings
+    ALTER COLUMN booking_start TYPE TIMESTAMP USING booking_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN booking_end TYPE TIMESTAMP USING booking_end AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_no_overlap
+    EXCLUDE USING gist (
+        photographer_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled');
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_room_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tsrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled' AND room_id IS NOT NULL);
@@ -0,0 +1,35 @@
+-- Время записей хранится с часовым поясом. Прежние значения были местным
+-- временем студии. Пояс указан явно, чтобы результат не зависел от TimeZone
+-- сессии; если STUDIO_TIMEZONE отличается от Europe/Moscow, замените его
+-- здесь и в down-миграции перед применением
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_no_overlap;
+ALTER TABLE bookings DROP CONSTRAINT IF EXISTS bookings_room_no_overlap;
+
+ALTER TABLE bookings
+    ALTER COLUMN booking_start TYPE TIMESTAMPTZ USING booking_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN booking_end TYPE TIMESTAMPTZ USING booking_end AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE booking_history
+    ALTER COLUMN booking_start TYPE TIMESTAMPTZ USING booking_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN booking_end TYPE TIMESTAMPTZ USING booking_end AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE waitlist
+    ALTER COLUMN offered_start TYPE TIMESTAMPTZ USING offered_start AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN offered_end TYPE TIMESTAMPTZ USING offered_end AT TIME ZONE 'Europe/Moscow',
+    ALTER COLUMN hold_until TYPE TIMESTAMPTZ USING hold_until AT TIME ZONE 'Europe/Moscow';
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_no_overlap
+    EXCLUDE USING gist (
+        photographer_id WITH =,
+        tstzrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled');
+
+ALTER TABLE bookings
+    ADD CONSTRAINT bookings_room_no_overlap
+    EXCLUDE USING gist (
+        room_id WITH =,
+        tstzrange(booking_start, booking_end) WITH &&
+    )
+    WHERE (status <> 'cancelled' AND room_id IS NOT NULL);
@@ -0,0 +1,3 @@
+ALTER TABLE photographers DROP CONSTRAINT IF EXISTS photographers_telegram_id_positive;
+ALTER TABLE clients DROP CONSTRAINT IF EXISTS clients_telegram_id_positive;
+DROP TABLE IF EXISTS telegram_id_audit;
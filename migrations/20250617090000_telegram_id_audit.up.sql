@@ -0,0 +1,43 @@
+-- Раньше telegram_id клиентов при регистрации приводился к i32, и ID больше
+-- 2147483647 сохранялись по модулю 2^32. В аудит попадают только строки,
+-- которые точно испорчены:
+-- truncated — ID отрицательный или нулевой, у личных чатов таких не бывает;
+-- collision — ID совпадает с обрезанным полным ID другого клиента или
+-- фотографа, то есть строка принадлежит ему или перепутана с ним.
+-- Положительные ID без совпадений не отличить от настоящих, их не трогаем
+CREATE TABLE IF NOT EXISTS telegram_id_audit (
+    id SERIAL PRIMARY KEY,
+    source TEXT NOT NULL,
+    row_id INTEGER NOT NULL,
+    telegram_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    username TEXT,
+    reason TEXT NOT NULL,
+    found_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Полные ID и их значения после приведения к i32
+WITH wrapped AS (
+    SELECT (telegram_id + 2147483648) % 4294967296 - 2147483648 AS telegram_id FROM clients WHERE telegram_id > 2147483647
+    UNION
+    SELECT (telegram_id + 2147483648) % 4294967296 - 2147483648 FROM archived_clients WHERE telegram_id > 2147483647
+    UNION
+    SELECT (telegram_id + 2147483648) % 4294967296 - 2147483648 FROM photographers WHERE telegram_id > 2147483647
+)
+INSERT INTO telegram_id_audit (source, row_id, telegram_id, name, username, reason)
+SELECT 'clients', id, telegram_id, name, username,
+       CASE WHEN telegram_id <= 0 THEN 'truncated' ELSE 'collision' END
+FROM clients WHERE telegram_id <= 0 OR telegram_id IN (SELECT telegram_id FROM wrapped)
+UNION ALL
+SELECT 'archived_clients', id, telegram_id, name, username,
+       CASE WHEN telegram_id <= 0 THEN 'truncated' ELSE 'collision' END
+FROM archived_clients WHERE telegram_id <= 0 OR telegram_id IN (SELECT telegram_id FROM wrapped)
+UNION ALL
+SELECT 'photographers', id, telegram_id, name, NULL,
+       CASE WHEN telegram_id <= 0 THEN 'truncated' ELSE 'collision' END
+FROM photographers WHERE telegram_id <= 0 OR telegram_id IN (SELECT telegram_id FROM wrapped);
+
+-- ID пользователей Telegram положительные. Новые строки проверяются сразу,
+-- существующие — после разбора аудита
+ALTER TABLE clients ADD CONSTRAINT clients_telegram_id_positive CHECK (telegram_id > 0) NOT VALID;
+ALTER TABLE photographers ADD CONSTRAINT photographers_telegram_id_positive CHECK (telegram_id > 0) NOT VALID;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS bookings_new_confirmation_requested_at_idx;
+CREATE INDEX IF NOT EXISTS bookings_new_created_at_idx ON bookings (created_at) WHERE status = 'new';
+ALTER TABLE bookings DROP COLUMN IF EXISTS confirmation_requested_at;
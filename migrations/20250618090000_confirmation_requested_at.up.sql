@@ -0,0 +1,7 @@
+-- Срок подтверждения фотографом отсчитывается от запроса подтверждения:
+-- при создании записи и после каждого переноса. created_at при переносе не меняется
+ALTER TABLE bookings ADD COLUMN confirmation_requested_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP;
+UPDATE bookings SET confirmation_requested_at = created_at;
+
+DROP INDEX IF EXISTS bookings_new_created_at_idx;
+CREATE INDEX IF NOT EXISTS bookings_new_confirmation_requested_at_idx ON bookings (confirmation_requested_at) WHERE status = 'new';
@@ -0,0 +1,586 @@
+use std::error::Error;
+
+use log::error;
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup, Message, ReplyMarkup}};
+use time::macros::format_description;
+
+use crate::catalog;
+use crate::config;
+use crate::models::{BookingStatus, Photographer, TelegramId};
+use crate::pricing;
+use crate::promo;
+use crate::session::{UserSession, UserStep};
+
+const BOOKINGS_PER_PAGE: i64 = 5;
+
+const STATUSES: [BookingStatus; 4] = [
+    BookingStatus::New,
+    BookingStatus::Confirmed,
+    BookingStatus::Completed,
+    BookingStatus::Cancelled,
+];
+
+fn status_key(status: BookingStatus) -> &'static str {
+    match status {
+        BookingStatus::New => "new",
+        BookingStatus::Confirmed => "confirmed",
+        BookingStatus::Completed => "completed",
+        BookingStatus::Cancelled => "cancelled",
+    }
+}
+
+pub fn is_admin(telegram_id: TelegramId) -> bool {
+    config::get().admin_telegram_ids.contains(&telegram_id)
+}
+
+pub async fn show_admin_menu(bot: &Bot, chat_id: ChatId) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let buttons: Vec<Vec<KeyboardButton>> = vec![
+        vec![KeyboardButton::new("Фотографы"), KeyboardButton::new("Услуги")],
+        vec![KeyboardButton::new("Записи"), KeyboardButton::new("Найти запись")],
+        vec![KeyboardButton::new("Скидки"), KeyboardButton::new("Промокоды")],
+    ];
+    let keyboard = KeyboardMarkup::new(buttons).resize_keyboard();
+    bot.send_message(chat_id, "Панель администратора")
+        .reply_markup(ReplyMarkup::Keyboard(keyboard))
+        .await?;
+    Ok(())
+}
+
+// "Имя; telegram_id", telegram_id можно не указывать
+fn parse_photographer(text: &str) -> Option<(String, Option<TelegramId>)> {
+    let mut parts = text.split(';').map(str::trim);
+    let name = parts.next().filter(|n| !n.is_empty())?.to_string();
+    let telegram_id = match parts.next() {
+        Some(id) if !id.is_empty() => Some(TelegramId(id.parse().ok()?)),
+        _ => None,
+    };
+    Some((name, telegram_id))
+}
+
+/// Текстовые сообщения администратора: кнопки меню и ввод данных.
+pub async fn handle_message(bot: &Bot, msg: &Message, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let text = msg.text().unwrap_or("").trim();
+
+    match text {
+        "Фотографы" => {
+            session.step = UserStep::AdminMenu;
+            return show_photographers(bot, chat_id, pool, None).await;
+        }
+        "Услуги" => {
+            session.step = UserStep::AdminMenu;
+            return catalog::show_catalog(bot, chat_id, pool, None).await;
+        }
+        "Записи" => {
+            session.step = UserStep::AdminMenu;
+            return show_bookings(bot, chat_id, pool, 0, None).await;
+        }
+        "Найти запись" => {
+            session.step = UserStep::AdminFindBooking;
+            bot.send_message(chat_id, "Введите номер записи:").await?;
+            return Ok(());
+        }
+        "Скидки" => {
+            session.step = UserStep::AdminMenu;
+            return show_price_rules(bot, chat_id, pool, None).await;
+        }
+        "Промокоды" => {
+            session.step = UserStep::AdminMenu;
+            return show_promo_codes(bot, chat_id, pool, None).await;
+        }
+        _ => {}
+    }
+
+    match session.step {
+        UserStep::AdminAddPhotographer | UserStep::AdminEditPhotographer => {
+            let Some((name, telegram_id)) = parse_photographer(text) else {
+                bot.send_message(chat_id, "Неверный формат. Введите: Имя; Telegram ID").await?;
+                return Ok(());
+            };
+            let photographer_id = match (session.step, session.edit_target_id) {
+                (UserStep::AdminEditPhotographer, Some(photographer_id)) => {
+                    sqlx::query!(
+                        "UPDATE photographers SET name = $2, telegram_id = $3 WHERE id = $1",
+                        photographer_id,
+                        name,
+                        telegram_id as _
+                    )
+                    .execute(pool)
+                    .await?;
+                    photographer_id
+                }
+                _ => sqlx::query_scalar!(
+                    "INSERT INTO photographers (name, telegram_id) VALUES ($1, $2) RETURNING id",
+                    name,
+                    telegram_id as _
+                )
+                .fetch_one(pool)
+                .await?,
+            };
+            session.step = UserStep::AdminMenu;
+            session.edit_target_id = None;
+            show_photographer(bot, chat_id, pool, photographer_id, None).await?;
+        }
+        UserStep::AdminFindBooking => {
+            match text.trim_start_matches('№').trim_start_matches('#').parse::<i32>() {
+                Ok(booking_id) => {
+                    session.step = UserStep::AdminMenu;
+                    show_booking(bot, chat_id, pool, booking_id, None).await?;
+                }
+                Err(_) => {
+                    bot.send_message(chat_id, "Введите номер записи числом").await?;
+                }
+            }
+        }
+        UserStep::AdminAddPriceRule => {
+            let Some((weekdays, start_time, end_time, percent_off)) = pricing::parse_rule(text) else {
+                bot.send_message(chat_id, "Неверный формат. Введите дни, часы и скидку, например: Пн-Пт 10:00-13:00 20%").await?;
+                return Ok(());
+            };
+            pricing::add_rule(pool, weekdays, start_time, end_time, percent_off).await?;
+            session.step = UserStep::AdminMenu;
+            show_price_rules(bot, chat_id, pool, None).await?;
+        }
+        UserStep::AdminAddPromoCode => {
+            let Some(promo_code) = promo::parse_promo(text) else {
+                bot.send_message(chat_id, "Неверный формат. Введите: КОД; скидка в % или рублях; период; лимит; номера услуг").await?;
+                return Ok(());
+            };
+            match promo::add(pool, promo_code).await {
+                Ok(()) => {
+                    session.step = UserStep::AdminMenu;
+                    show_promo_codes(bot, chat_id, pool, None).await?;
+                }
+                // 23505 — такой код уже есть
+                Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505") => {
+                    bot.send_message(chat_id, "Такой промокод уже существует, введите другой код").await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        _ => {
+            show_admin_menu(bot, chat_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Кнопки панели администратора, `data` начинается с "admin:".
+pub async fn handle_callback(bot: &Bot, msg: &Message, data: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let edit = Some(msg);
+    let parts: Vec<&str> = data.split(':').collect();
+
+    match parts.as_slice() {
+        ["admin", "photographers"] => show_photographers(bot, chat_id, pool, edit).await?,
+        ["admin", "photographer", id] => show_photographer(bot, chat_id, pool, id.parse()?, edit).await?,
+        ["admin", "add_photographer"] => {
+            session.step = UserStep::AdminAddPhotographer;
+            session.edit_target_id = None;
+            bot.send_message(chat_id, "Введите имя и Telegram ID нового фотографа через точку с запятой, например:\nАнна Смирнова; 123456789").await?;
+        }
+        ["admin", "edit_photographer", id] => {
+            session.step = UserStep::AdminEditPhotographer;
+            session.edit_target_id = Some(id.parse()?);
+            bot.send_message(chat_id, "Введите новые имя и Telegram ID через точку с запятой, например:\nАнна Смирнова; 123456789").await?;
+        }
+        ["admin", "toggle_photographer", id] => {
+            let photographer_id: i32 = id.parse()?;
+            sqlx::query!("UPDATE photographers SET is_active = NOT is_active WHERE id = $1", photographer_id)
+                .execute(pool)
+                .await?;
+            show_photographer(bot, chat_id, pool, photographer_id, edit).await?;
+        }
+        ["admin", "toggle_catalog_access", id] => {
+            let photographer_id: i32 = id.parse()?;
+            sqlx::query!("UPDATE photographers SET can_manage_catalog = NOT can_manage_catalog WHERE id = $1", photographer_id)
+                .execute(pool)
+                .await?;
+            show_photographer(bot, chat_id, pool, photographer_id, edit).await?;
+        }
+        ["admin", "photographer_services", id] => show_photographer_services(bot, chat_id, pool, id.parse()?, msg).await?,
+        ["admin", "link", photographer_id, service_id] => {
+            let (photographer_id, service_id): (i32, i32) = (photographer_id.parse()?, service_id.parse()?);
+            let removed = sqlx::query!(
+                "DELETE FROM photographer_services WHERE photographer_id = $1 AND service_id = $2",
+                photographer_id,
+                service_id
+            )
+            .execute(pool)
+            .await?
+            .rows_affected();
+            if removed == 0 {
+                sqlx::query!(
+                    "INSERT INTO photographer_services (photographer_id, service_id) VALUES ($1, $2)
+                     ON CONFLICT DO NOTHING",
+                    photographer_id,
+                    service_id
+                )
+                .execute(pool)
+                .await?;
+            }
+            show_photographer_services(bot, chat_id, pool, photographer_id, msg).await?;
+        }
+        ["admin", "price_rules"] => show_price_rules(bot, chat_id, pool, edit).await?,
+        ["admin", "add_rule"] => {
+            session.step = UserStep::AdminAddPriceRule;
+            bot.send_message(chat_id, "Введите дни, часы и скидку, например:\nПн-Пт 10:00-13:00 20%").await?;
+        }
+        ["admin", "delete_rule", id] => {
+            pricing::delete_rule(pool, id.parse()?).await?;
+            show_price_rules(bot, chat_id, pool, edit).await?;
+        }
+        ["admin", "promo_codes"] => show_promo_codes(bot, chat_id, pool, edit).await?,
+        ["admin", "add_promo"] => {
+            session.step = UserStep::AdminAddPromoCode;
+            bot.send_message(
+                chat_id,
+                "Введите промокод через точку с запятой:\nКОД; скидка; период; лимит использований; номера услуг\n\n\
+                Скидка — в процентах (15%) или рублях (500). Остальные поля можно пропустить или поставить «-», например:\n\
+                LETO; 15%; 01.06.2025-31.08.2025; 50; 1,3\nDRUG500; 500",
+            ).await?;
+        }
+        ["admin", "toggle_promo", id] => {
+            promo::toggle(pool, id.parse()?).await?;
+            show_promo_codes(bot, chat_id, pool, edit).await?;
+        }
+        ["admin", "bookings", page] => show_bookings(bot, chat_id, pool, page.parse()?, edit).await?,
+        ["admin", "booking", id] => show_booking(bot, chat_id, pool, id.parse()?, edit).await?,
+        ["admin", "set_status", id, status] => {
+            let booking_id: i32 = id.parse()?;
+            let Some(status) = STATUSES.iter().copied().find(|s| status_key(*s) == *status) else {
+                return Ok(());
+            };
+            match override_booking_status(pool, booking_id, status).await {
+                Ok(()) => {
+                    notify_status_override(bot, pool, booking_id, status).await;
+                    show_booking(bot, chat_id, pool, booking_id, edit).await?;
+                }
+                // 23P01 — время уже занято другой активной записью
+                Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23P01") => {
+                    bot.send_message(chat_id, format!("Нельзя вернуть запись №{}: это время уже занято другой записью", booking_id)).await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+// Скидки студии применяются ко всем фотографам и услугам
+async fn show_price_rules(bot: &Bot, chat_id: ChatId, pool: &PgPool, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let rules = pricing::get_rules(pool).await?;
+
+    let text = if rules.is_empty() {
+        "Скидок пока нет".to_string()
+    } else {
+        "Скидки по дням и часам (применяется наибольшая подходящая). Нажмите, чтобы удалить:".to_string()
+    };
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = rules
+        .iter()
+        .map(|rule| vec![InlineKeyboardButton::callback(
+            format!("❌ {}", rule.describe()),
+            format!("admin:delete_rule:{}", rule.id),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("➕ Добавить скидку", "admin:add_rule")]);
+
+    reply(bot, chat_id, edit, text, InlineKeyboardMarkup::new(keyboard)).await
+}
+
+async fn show_promo_codes(bot: &Bot, chat_id: ChatId, pool: &PgPool, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let promo_codes = promo::get_all(pool).await?;
+
+    let text = if promo_codes.is_empty() {
+        "Промокодов пока нет".to_string()
+    } else {
+        "Промокоды (✅ — действует, 🚫 — выключен). Нажмите, чтобы включить или выключить:".to_string()
+    };
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = promo_codes
+        .iter()
+        .map(|p| vec![InlineKeyboardButton::callback(
+            format!("{} {}", active_mark(p.is_active), p.describe()),
+            format!("admin:toggle_promo:{}", p.id),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("➕ Добавить промокод", "admin:add_promo")]);
+
+    reply(bot, chat_id, edit, text, InlineKeyboardMarkup::new(keyboard)).await
+}
+
+// Отправляет новое сообщение или заменяет текст сообщения с нажатой кнопкой
+pub(crate) async fn reply(bot: &Bot, chat_id: ChatId, edit: Option<&Message>, text: String, keyboard: InlineKeyboardMarkup) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match edit {
+        Some(msg) => {
+            bot.edit_message_text(chat_id, msg.id, text)
+                .reply_markup(keyboard)
+                .await?;
+        }
+        None => {
+            bot.send_message(chat_id, text)
+                .reply_markup(ReplyMarkup::InlineKeyboard(keyboard))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn active_mark(is_active: bool) -> &'static str {
+    if is_active { "✅" } else { "🚫" }
+}
+
+async fn show_photographers(bot: &Bot, chat_id: ChatId, pool: &PgPool, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let photographers = sqlx::query_as::<_, Photographer>("SELECT * FROM photographers ORDER BY id")
+        .fetch_all(pool)
+        .await?;
+
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = photographers
+        .iter()
+        .map(|p| vec![InlineKeyboardButton::callback(
+            format!("{} {}", active_mark(p.is_active), p.name),
+            format!("admin:photographer:{}", p.id),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("➕ Добавить фотографа", "admin:add_photographer")]);
+
+    reply(bot, chat_id, edit, "Фотографы студии:".to_string(), InlineKeyboardMarkup::new(keyboard)).await
+}
+
+async fn show_photographer(bot: &Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let photographer = sqlx::query!(
+        r#"SELECT name, telegram_id as "telegram_id: TelegramId", is_active, can_manage_catalog FROM photographers WHERE id = $1"#,
+        photographer_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let services = sqlx::query_scalar!(
+        "SELECT s.name FROM services s
+         JOIN photographer_services ps ON s.id = ps.service_id
+         WHERE ps.photographer_id = $1
+         ORDER BY s.id",
+        photographer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let text = format!(
+        "Фотограф №{}\n\nИмя: {}\nTelegram ID: {}\nСтатус: {}\nУслуги: {}\nУправление каталогом: {}",
+        photographer_id,
+        photographer.name,
+        photographer.telegram_id.map(|id| id.to_string()).unwrap_or_else(|| "не указан".to_string()),
+        if photographer.is_active { "активен" } else { "деактивирован" },
+        if services.is_empty() { "нет".to_string() } else { services.join(", ") },
+        if photographer.can_manage_catalog { "да" } else { "нет" }
+    );
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("✏️ Изменить", format!("admin:edit_photographer:{}", photographer_id))],
+        vec![InlineKeyboardButton::callback("📸 Услуги", format!("admin:photographer_services:{}", photographer_id))],
+        vec![InlineKeyboardButton::callback(
+            if photographer.is_active { "🚫 Деактивировать" } else { "✅ Активировать" },
+            format!("admin:toggle_photographer:{}", photographer_id),
+        )],
+        vec![InlineKeyboardButton::callback(
+            if photographer.can_manage_catalog { "🔒 Закрыть доступ к каталогу" } else { "🔑 Дать доступ к каталогу" },
+            format!("admin:toggle_catalog_access:{}", photographer_id),
+        )],
+        vec![InlineKeyboardButton::callback("⟵ Назад", "admin:photographers")],
+    ]);
+
+    reply(bot, chat_id, edit, text, keyboard).await
+}
+
+async fn show_photographer_services(bot: &Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32, msg: &Message) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let services = sqlx::query!(
+        r#"SELECT s.id, s.name, s.is_active,
+                  EXISTS (
+                      SELECT 1 FROM photographer_services ps
+                      WHERE ps.photographer_id = $1 AND ps.service_id = s.id
+                  ) as "linked!"
+         FROM services s
+         ORDER BY s.sort_order, s.id"#,
+        photographer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = services
+        .iter()
+        .map(|s| vec![InlineKeyboardButton::callback(
+            format!(
+                "{} {}{}",
+                if s.linked { "✅" } else { "➕" },
+                s.name,
+                if s.is_active { "" } else { " (в архиве)" }
+            ),
+            format!("admin:link:{}:{}", photographer_id, s.id),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("⟵ Назад", format!("admin:photographer:{}", photographer_id))]);
+
+    reply(bot, chat_id, Some(msg), "Услуги фотографа (нажмите, чтобы добавить или убрать):".to_string(), InlineKeyboardMarkup::new(keyboard)).await
+}
+
+// Ближайшие активные записи всех фотографов
+async fn show_bookings(bot: &Bot, chat_id: ChatId, pool: &PgPool, page: i64, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let bookings = sqlx::query!(
+        r#"SELECT b.id, b.booking_start::timestamp as "booking_start!", b.status as "status: BookingStatus", p.name as photographer_name
+         FROM bookings b
+         JOIN photographers p ON b.photographer_id = p.id
+         WHERE b.booking_start >= CURRENT_TIMESTAMP
+         AND b.status IN ('new', 'confirmed')
+         ORDER BY b.booking_start
+         LIMIT $1 OFFSET $2"#,
+        BOOKINGS_PER_PAGE + 1,
+        page * BOOKINGS_PER_PAGE
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let format = format_description!("[day].[month] [hour]:[minute]");
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = bookings
+        .iter()
+        .take(BOOKINGS_PER_PAGE as usize)
+        .map(|b| vec![InlineKeyboardButton::callback(
+            format!("№{} {} {} {}", b.id, b.booking_start.format(&format).unwrap(), b.photographer_name, b.status),
+            format!("admin:booking:{}", b.id),
+        )])
+        .collect();
+
+    let mut nav_buttons = vec![];
+    if page > 0 {
+        nav_buttons.push(InlineKeyboardButton::callback("⬅️ Назад", format!("admin:bookings:{}", page - 1)));
+    }
+    if bookings.len() as i64 > BOOKINGS_PER_PAGE {
+        nav_buttons.push(InlineKeyboardButton::callback("Вперед ➡️", format!("admin:bookings:{}", page + 1)));
+    }
+    if !nav_buttons.is_empty() {
+        keyboard.push(nav_buttons);
+    }
+
+    let text = if bookings.is_empty() { "Предстоящих записей нет" } else { "Предстоящие записи:" };
+    reply(bot, chat_id, edit, text.to_string(), InlineKeyboardMarkup::new(keyboard)).await
+}
+
+async fn show_booking(bot: &Bot, chat_id: ChatId, pool: &PgPool, booking_id: i32, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let booking = sqlx::query!(
+        r#"SELECT b.booking_start::timestamp as "booking_start!", b.booking_end::timestamp as "booking_end!", b.status as "status: BookingStatus",
+                  c.name as client_name, p.name as photographer_name, s.name as service_name
+         FROM bookings b
+         JOIN clients c ON b.client_id = c.id
+         JOIN photographers p ON b.photographer_id = p.id
+         JOIN services s ON b.service_id = s.id
+         WHERE b.id = $1"#,
+        booking_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(booking) = booking else {
+        bot.send_message(chat_id, format!("Запись №{} не найдена", booking_id)).await?;
+        return Ok(());
+    };
+
+    let format = format_description!("[day].[month].[year] [hour]:[minute]");
+    let time_format = format_description!("[hour]:[minute]");
+    let text = format!(
+        "Запись №{}\n\nКлиент: {}\nФотограф: {}\nУслуга: {}\nВремя: {} - {}\nСтатус: {}\n\nИзменить статус:",
+        booking_id,
+        booking.client_name,
+        booking.photographer_name,
+        booking.service_name,
+        booking.booking_start.format(&format).unwrap(),
+        booking.booking_end.format(&time_format).unwrap(),
+        booking.status
+    );
+
+    // Администратор может выставить любой статус, в том числе вне обычных переходов
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = STATUSES
+        .iter()
+        .filter(|s| **s != booking.status)
+        .map(|s| vec![InlineKeyboardButton::callback(
+            s.to_string(),
+            format!("admin:set_status:{}:{}", booking_id, status_key(*s)),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("⟵ К записям", "admin:bookings:0")]);
+
+    reply(bot, chat_id, edit, text, InlineKeyboardMarkup::new(keyboard)).await
+}
+
+// Смена статуса в обход can_transition_to; прежний статус сохраняется в booking_history
+async fn override_booking_status(pool: &PgPool, booking_id: i32, status: BookingStatus) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "INSERT INTO booking_history (booking_id, booking_start, booking_end, status, room_id, reason)
+         SELECT id, booking_start, booking_end, status, room_id, 'admin_override'
+         FROM bookings WHERE id = $1",
+        booking_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE bookings SET status = $2 WHERE id = $1",
+        booking_id,
+        status as BookingStatus
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn notify_status_override(bot: &Bot, pool: &PgPool, booking_id: i32, status: BookingStatus) {
+    let recipients = sqlx::query!(
+        r#"SELECT c.telegram_id as "client_telegram_id: TelegramId", p.telegram_id as "photographer_telegram_id: TelegramId"
+         FROM bookings b
+         JOIN clients c ON b.client_id = c.id
+         JOIN photographers p ON b.photographer_id = p.id
+         WHERE b.id = $1"#,
+        booking_id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    let recipients = match recipients {
+        Ok(Some(recipients)) => recipients,
+        Ok(None) => return,
+        Err(e) => {
+            error!("Failed to load recipients for booking {}: {}", booking_id, e);
+            return;
+        }
+    };
+
+    let text = format!("ℹ️ Администратор студии изменил статус записи №{}: {}", booking_id, status);
+    let chats = std::iter::once(Some(recipients.client_telegram_id)).chain(std::iter::once(recipients.photographer_telegram_id));
+    for telegram_id in chats.flatten() {
+        if let Err(e) = bot.send_message(ChatId::from(telegram_id), text.clone()).await {
+            error!("Failed to notify about status override of booking {}: {}", booking_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_photographer_keeps_ids_above_i32() {
+        let (name, telegram_id) = parse_photographer("Алексей Иванов; 6577990046").unwrap();
+        assert_eq!(name, "Алексей Иванов");
+        assert_eq!(telegram_id, Some(TelegramId(6_577_990_046)));
+    }
+
+    #[test]
+    fn parse_photographer_without_id() {
+        assert_eq!(parse_photographer("Алексей Иванов"), Some(("Алексей Иванов".to_string(), None)));
+        assert_eq!(parse_photographer("Алексей Иванов; abc"), None);
+    }
+}
@@ -0,0 +1,313 @@
+use sqlx::{PgExecutor, PgPool};
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+use crate::config;
+use crate::handlers::get_free_slots;
+use crate::slots::Interval;
+
+// Назначение фотографа для записи к «любому фотографу». Стратегия задаёт
+// порядок кандидатов, назначается первый, у которого выбранное время свободно
+
+// Фотограф, который оказывает услугу, и его показатели на момент записи
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub photographer_id: i32,
+    // Длительность активных записей за неделю и месяц съёмки
+    pub week_minutes: i64,
+    pub month_minutes: i64,
+    pub rating: Option<f64>,
+    // Когда фотографу последний раз назначили запись
+    pub last_booked_at: Option<OffsetDateTime>,
+}
+
+pub trait AssignmentStrategy: Send + Sync {
+    /// Упорядочивает кандидатов: первым идёт тот, кому запись достанется в первую очередь.
+    fn rank(&self, candidates: &mut [Candidate]);
+}
+
+/// Меньше всего занятых часов за неделю.
+pub struct LeastLoaded;
+
+impl AssignmentStrategy for LeastLoaded {
+    fn rank(&self, candidates: &mut [Candidate]) {
+        candidates.sort_by_key(|c| (c.week_minutes, c.photographer_id));
+    }
+}
+
+/// Самый высокий рейтинг; без отзывов — в конце. При равенстве — менее загруженный.
+pub struct BestRated;
+
+impl AssignmentStrategy for BestRated {
+    fn rank(&self, candidates: &mut [Candidate]) {
+        candidates.sort_by(|a, b| {
+            b.rating
+                .unwrap_or(f64::MIN)
+                .total_cmp(&a.rating.unwrap_or(f64::MIN))
+                .then(a.week_minutes.cmp(&b.week_minutes))
+                .then(a.photographer_id.cmp(&b.photographer_id))
+        });
+    }
+}
+
+/// По очереди: запись получает тот, кому её дольше всех не назначали.
+pub struct RoundRobin;
+
+impl AssignmentStrategy for RoundRobin {
+    fn rank(&self, candidates: &mut [Candidate]) {
+        // None меньше любого Some — фотограф без записей идёт первым
+        candidates.sort_by_key(|c| (c.last_booked_at, c.photographer_id));
+    }
+}
+
+// Оценка фотографа без отзывов
+const NEUTRAL_RATING: f64 = 4.0;
+
+/// Баланс загрузки и рейтинга: запись получает фотограф с наименьшим баллом
+/// `часы за неделю * week_weight + часы за месяц * month_weight - рейтинг * rating_weight`.
+/// С rating_weight = 1 звезда рейтинга перевешивает час занятости.
+#[derive(Debug, Clone, Copy)]
+pub struct Balanced {
+    pub week_weight: f64,
+    pub month_weight: f64,
+    pub rating_weight: f64,
+}
+
+impl Balanced {
+    fn score(&self, candidate: &Candidate) -> f64 {
+        candidate.week_minutes as f64 / 60.0 * self.week_weight
+            + candidate.month_minutes as f64 / 60.0 * self.month_weight
+            - candidate.rating.unwrap_or(NEUTRAL_RATING) * self.rating_weight
+    }
+}
+
+impl AssignmentStrategy for Balanced {
+    fn rank(&self, candidates: &mut [Candidate]) {
+        candidates.sort_by(|a, b| {
+            self.score(a)
+                .total_cmp(&self.score(b))
+                .then(a.photographer_id.cmp(&b.photographer_id))
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StrategyKind {
+    LeastLoaded,
+    BestRated,
+    RoundRobin,
+    Balanced,
+}
+
+impl StrategyKind {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "least_loaded" => Some(StrategyKind::LeastLoaded),
+            "best_rated" => Some(StrategyKind::BestRated),
+            "round_robin" => Some(StrategyKind::RoundRobin),
+            "balanced" => Some(StrategyKind::Balanced),
+            _ => None,
+        }
+    }
+}
+
+/// Стратегия из настроек студии.
+pub fn strategy() -> Box<dyn AssignmentStrategy> {
+    let config = config::get();
+    match config.assignment_strategy {
+        StrategyKind::LeastLoaded => Box::new(LeastLoaded),
+        StrategyKind::BestRated => Box::new(BestRated),
+        StrategyKind::RoundRobin => Box::new(RoundRobin),
+        StrategyKind::Balanced => Box::new(config.balance_weights),
+    }
+}
+
+/// Активные фотографы с этой услугой, упорядоченные стратегией студии.
+pub async fn candidates<'e>(
+    executor: impl PgExecutor<'e>,
+    service_id: i32,
+    booking_start: PrimitiveDateTime,
+) -> Result<Vec<Candidate>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT p.id as "photographer_id!",
+                  COALESCE((
+                      SELECT SUM(EXTRACT(EPOCH FROM b.booking_end - b.booking_start) / 60)
+                      FROM bookings b
+                      WHERE b.photographer_id = p.id
+                      AND b.status <> 'cancelled'
+                      AND date_trunc('week', b.booking_start) = date_trunc('week', $2::timestamp)
+                  ), 0)::INT8 as "week_minutes!",
+                  COALESCE((
+                      SELECT SUM(EXTRACT(EPOCH FROM b.booking_end - b.booking_start) / 60)
+                      FROM bookings b
+                      WHERE b.photographer_id = p.id
+                      AND b.status <> 'cancelled'
+                      AND date_trunc('month', b.booking_start) = date_trunc('month', $2::timestamp)
+                  ), 0)::INT8 as "month_minutes!",
+                  (SELECT AVG(r.rating)::FLOAT8 FROM reviews r WHERE r.photographer_id = p.id) as rating,
+                  (SELECT MAX(b.created_at) FROM bookings b WHERE b.photographer_id = p.id) as last_booked_at
+         FROM photographers p
+         JOIN photographer_services ps ON ps.photographer_id = p.id
+         WHERE ps.service_id = $1
+         AND p.is_active
+         ORDER BY p.id"#,
+        service_id,
+        booking_start
+    )
+    .fetch_all(executor)
+    .await?;
+
+    let mut candidates: Vec<Candidate> = rows
+        .into_iter()
+        .map(|row| Candidate {
+            photographer_id: row.photographer_id,
+            week_minutes: row.week_minutes,
+            month_minutes: row.month_minutes,
+            rating: row.rating,
+            last_booked_at: row.last_booked_at,
+        })
+        .collect();
+    strategy().rank(&mut candidates);
+    Ok(candidates)
+}
+
+/// Фотограф, которого назначат на это время, если подтвердить запись сейчас.
+/// Показывается в сводке перед подтверждением.
+pub async fn preview(
+    pool: &PgPool,
+    service_id: i32,
+    booking_start: PrimitiveDateTime,
+    booking_end: PrimitiveDateTime,
+) -> Result<Option<i32>, sqlx::Error> {
+    let slot = Interval::from_times(booking_start.time(), booking_end.time()).label();
+    for candidate in candidates(pool, service_id, booking_start).await? {
+        if get_free_slots(pool, candidate.photographer_id, service_id, booking_start).await?.contains(&slot) {
+            return Ok(Some(candidate.photographer_id));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    fn candidate(photographer_id: i32, rating: Option<f64>) -> Candidate {
+        Candidate { photographer_id, week_minutes: 0, month_minutes: 0, rating, last_booked_at: None }
+    }
+
+    fn order(strategy: &dyn AssignmentStrategy, mut candidates: Vec<Candidate>) -> Vec<i32> {
+        strategy.rank(&mut candidates);
+        candidates.iter().map(|c| c.photographer_id).collect()
+    }
+
+    fn loaded(photographer_id: i32, week_minutes: i64, rating: Option<f64>) -> Candidate {
+        Candidate { week_minutes, ..candidate(photographer_id, rating) }
+    }
+
+    #[test]
+    fn least_loaded_orders_by_week_load_then_id() {
+        let candidates = vec![loaded(3, 120, None), loaded(2, 60, Some(5.0)), loaded(1, 120, Some(4.0))];
+        assert_eq!(order(&LeastLoaded, candidates), vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn best_rated_breaks_ties_by_load_then_id() {
+        let candidates = vec![loaded(3, 60, Some(4.5)), loaded(2, 120, Some(4.5)), loaded(1, 60, Some(4.5)), loaded(4, 0, Some(4.9))];
+        assert_eq!(order(&BestRated, candidates), vec![4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn round_robin_puts_never_assigned_first() {
+        let mut recent = candidate(1, None);
+        recent.last_booked_at = Some(datetime!(2025-06-02 12:00 UTC));
+        let mut earlier = candidate(2, None);
+        earlier.last_booked_at = Some(datetime!(2025-06-01 12:00 UTC));
+        let never = candidate(3, None);
+        assert_eq!(order(&RoundRobin, vec![recent, earlier, never]), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn round_robin_breaks_ties_by_id() {
+        let at = Some(datetime!(2025-06-01 12:00 UTC));
+        let candidates = vec![
+            Candidate { last_booked_at: at, ..candidate(4, None) },
+            candidate(3, None),
+            Candidate { last_booked_at: at, ..candidate(2, None) },
+            candidate(1, None),
+        ];
+        assert_eq!(order(&RoundRobin, candidates), vec![1, 3, 2, 4]);
+    }
+
+    // Раздаёт записи по одной, как при последовательных записях к «любому фотографу»,
+    // и возвращает число записей у каждого фотографа
+    fn simulate(strategy: &dyn AssignmentStrategy, candidates: &mut [Candidate], durations: &[i64]) -> Vec<(i32, usize)> {
+        let mut counts: Vec<(i32, usize)> = candidates.iter().map(|c| (c.photographer_id, 0)).collect();
+        for duration in durations {
+            strategy.rank(candidates);
+            let chosen = &mut candidates[0];
+            chosen.week_minutes += duration;
+            chosen.month_minutes += duration;
+            counts.iter_mut().find(|(id, _)| *id == chosen.photographer_id).unwrap().1 += 1;
+        }
+        counts.sort();
+        counts
+    }
+
+    fn spread(candidates: &[Candidate]) -> i64 {
+        let max = candidates.iter().map(|c| c.week_minutes).max().unwrap();
+        let min = candidates.iter().map(|c| c.week_minutes).min().unwrap();
+        max - min
+    }
+
+    const WEIGHTS: Balanced = Balanced { week_weight: 1.0, month_weight: 0.25, rating_weight: 1.0 };
+
+    #[test]
+    fn balanced_splits_equal_bookings_evenly() {
+        let mut candidates = vec![candidate(1, Some(4.5)), candidate(2, Some(4.5)), candidate(3, Some(4.5))];
+        let counts = simulate(&WEIGHTS, &mut candidates, &[60; 30]);
+        assert_eq!(counts, vec![(1, 10), (2, 10), (3, 10)]);
+    }
+
+    #[test]
+    fn balanced_keeps_load_within_one_booking() {
+        let mut candidates = vec![candidate(1, None), candidate(2, None), candidate(3, None), candidate(4, None)];
+        let durations: Vec<i64> = (0..50).map(|i| [60, 90, 120, 30][i % 4]).collect();
+        simulate(&WEIGHTS, &mut candidates, &durations);
+        assert!(spread(&candidates) <= 120, "spread {} minutes", spread(&candidates));
+    }
+
+    #[test]
+    fn balanced_prefers_rating_but_does_not_starve_others() {
+        let mut candidates = vec![candidate(1, Some(5.0)), candidate(2, Some(3.0))];
+        let counts = simulate(&WEIGHTS, &mut candidates, &[60; 20]);
+        // Лучший рейтинг даёт фору, но не больше разницы в звёздах (в часах)
+        assert!(counts[0].1 > counts[1].1);
+        assert!(counts[1].1 >= 8, "{:?}", counts);
+    }
+
+    #[test]
+    fn balanced_accounts_for_month_load() {
+        let mut busy = candidate(1, Some(4.0));
+        busy.month_minutes = 40 * 60;
+        let mut candidates = vec![busy, candidate(2, Some(4.0))];
+        WEIGHTS.rank(&mut candidates);
+        assert_eq!(candidates[0].photographer_id, 2);
+    }
+
+    #[test]
+    fn least_loaded_alternates_between_photographers() {
+        let mut candidates = vec![candidate(1, None), candidate(2, None)];
+        let counts = simulate(&LeastLoaded, &mut candidates, &[60; 10]);
+        assert_eq!(counts, vec![(1, 5), (2, 5)]);
+    }
+
+    #[test]
+    fn best_rated_puts_photographers_without_reviews_last() {
+        let mut candidates = vec![candidate(1, None), candidate(2, Some(3.5)), candidate(3, Some(4.8))];
+        BestRated.rank(&mut candidates);
+        let order: Vec<i32> = candidates.iter().map(|c| c.photographer_id).collect();
+        assert_eq!(order, vec![3, 2, 1]);
+    }
+}
@@ -0,0 +1,224 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::PgPool;
+use time::{Date, Time};
+
+use crate::clock;
+use crate::config;
+use crate::slots::{self, minutes_of, Interval, SlotRequest};
+
+// Доступность дней для календаря: рабочие часы, записи и удержания листа
+// ожидания за период загружаются одним запросом, слоты считаются так же,
+// как в get_free_slots. photographer_id = -1 — любой фотограф с этой услугой
+
+// Сколько свободных слотов в день считается «мало мест»
+const FEW_SLOTS: usize = 3;
+
+/// Загруженность рабочего дня для календаря.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Load {
+    Many,
+    Few,
+    Full,
+}
+
+impl Load {
+    pub fn of(free_slots: usize) -> Self {
+        match free_slots {
+            0 => Load::Full,
+            n if n <= FEW_SLOTS => Load::Few,
+            _ => Load::Many,
+        }
+    }
+
+    pub fn mark(&self) -> &'static str {
+        match self {
+            Load::Many => "🟢",
+            Load::Few => "🟡",
+            Load::Full => "🔴",
+        }
+    }
+}
+
+pub const LEGEND: &str = "🟢 свободно  🟡 мало мест  🔴 занято";
+
+struct DayRow {
+    date: Date,
+    start_time: Time,
+    end_time: Time,
+    duration: i32,
+    buffer_before: i32,
+    buffer_after: i32,
+    room_types: Option<Vec<String>>,
+    // Занятые интервалы фотографа в минутах, уже с буферами услуг
+    busy_starts: Vec<i32>,
+    busy_ends: Vec<i32>,
+    // Подходящие залы и их занятость
+    room_ids: Vec<i32>,
+    room_busy_ids: Vec<i32>,
+    room_busy_starts: Vec<i32>,
+    room_busy_ends: Vec<i32>,
+}
+
+impl DayRow {
+    fn free_slots(&self) -> Vec<Interval> {
+        let busy: Vec<Interval> = self
+            .busy_starts
+            .iter()
+            .zip(&self.busy_ends)
+            .map(|(start, end)| Interval::new(*start, *end))
+            .collect();
+
+        let rooms: Option<Vec<Vec<Interval>>> = match &self.room_types {
+            Some(room_types) if !room_types.is_empty() => Some(
+                self.room_ids
+                    .iter()
+                    .map(|room_id| {
+                        (0..self.room_busy_ids.len())
+                            .filter(|i| self.room_busy_ids[*i] == *room_id)
+                            .map(|i| Interval::new(self.room_busy_starts[i], self.room_busy_ends[i]))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        let request = SlotRequest {
+            duration: self.duration,
+            buffer_before: self.buffer_before,
+            buffer_after: self.buffer_after,
+            step: config::get().slot_step_minutes,
+        };
+        slots::free_slots(Interval::from_times(self.start_time, self.end_time), &busy, rooms.as_deref(), &request)
+    }
+}
+
+/// Свободные слоты услуги по рабочим дням периода [from, to]. Для любого
+/// фотографа слоты объединяются. Нерабочих дней в результате нет, у полностью
+/// занятых — пустой список.
+pub async fn free_slots(
+    pool: &PgPool,
+    photographer_id: i32,
+    service_id: i32,
+    from: Date,
+    to: Date,
+) -> Result<HashMap<Date, Vec<Interval>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"WITH days AS (
+             SELECT wh.photographer_id, wh.date, wh.start_time, wh.end_time
+             FROM resolved_working_hours($3, $4) wh
+             JOIN photographers p ON wh.photographer_id = p.id
+             WHERE wh.start_time < wh.end_time
+             AND (wh.photographer_id = $1
+                  OR ($1 = -1 AND p.is_active AND EXISTS (
+                      SELECT 1 FROM photographer_services ps
+                      WHERE ps.photographer_id = p.id AND ps.service_id = $2
+                  )))
+         ),
+         busy AS (
+             SELECT b.photographer_id, b.booking_start AS busy_start, b.booking_end AS busy_end,
+                    s.buffer_before, s.buffer_after
+             FROM bookings b
+             JOIN services s ON b.service_id = s.id
+             WHERE b.status <> 'cancelled'
+             AND DATE(b.booking_start) BETWEEN $3 AND $4
+             UNION ALL
+             SELECT w.offered_photographer_id, w.offered_start, w.offered_end,
+                    s.buffer_before, s.buffer_after
+             FROM waitlist w
+             JOIN services s ON w.service_id = s.id
+             WHERE w.status = 'offered'
+             AND w.hold_until > CURRENT_TIMESTAMP
+             AND DATE(w.offered_start) BETWEEN $3 AND $4
+         ),
+         room_busy AS (
+             SELECT b.room_id, r.room_type, DATE(b.booking_start) AS date,
+                    (EXTRACT(HOUR FROM b.booking_start::time) * 60 + EXTRACT(MINUTE FROM b.booking_start::time))::int - s.buffer_before AS busy_start,
+                    (EXTRACT(HOUR FROM b.booking_end::time) * 60 + EXTRACT(MINUTE FROM b.booking_end::time))::int + s.buffer_after AS busy_end
+             FROM bookings b
+             JOIN services s ON b.service_id = s.id
+             JOIN rooms r ON b.room_id = r.id
+             WHERE b.status <> 'cancelled'
+             AND DATE(b.booking_start) BETWEEN $3 AND $4
+         )
+         SELECT d.date as "date!", d.start_time as "start_time!", d.end_time as "end_time!",
+                COALESCE(ps.duration_override, s.duration) as "duration!",
+                s.buffer_before, s.buffer_after, s.room_types,
+                photographer_busy.starts as "busy_starts", photographer_busy.ends as "busy_ends",
+                ARRAY(SELECT r.id FROM rooms r WHERE r.room_type = ANY(s.room_types) AND r.is_active ORDER BY r.id) as "room_ids!",
+                rooms_busy.ids as "room_busy_ids", rooms_busy.starts as "room_busy_starts", rooms_busy.ends as "room_busy_ends"
+         FROM days d
+         JOIN services s ON s.id = $2
+         LEFT JOIN photographer_services ps ON ps.service_id = s.id AND ps.photographer_id = d.photographer_id
+         LEFT JOIN LATERAL (
+             SELECT ARRAY_AGG((EXTRACT(HOUR FROM busy.busy_start::time) * 60 + EXTRACT(MINUTE FROM busy.busy_start::time))::int - busy.buffer_before) AS starts,
+                    ARRAY_AGG((EXTRACT(HOUR FROM busy.busy_end::time) * 60 + EXTRACT(MINUTE FROM busy.busy_end::time))::int + busy.buffer_after) AS ends
+             FROM busy
+             WHERE busy.photographer_id = d.photographer_id AND DATE(busy.busy_start) = d.date
+         ) photographer_busy ON TRUE
+         LEFT JOIN LATERAL (
+             SELECT ARRAY_AGG(rb.room_id) AS ids, ARRAY_AGG(rb.busy_start) AS starts, ARRAY_AGG(rb.busy_end) AS ends
+             FROM room_busy rb
+             WHERE rb.date = d.date AND rb.room_type = ANY(s.room_types)
+         ) rooms_busy ON TRUE"#,
+        photographer_id,
+        service_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let now = clock::now();
+    let mut days: HashMap<Date, Vec<Interval>> = HashMap::new();
+    for row in rows {
+        let day = DayRow {
+            date: row.date,
+            start_time: row.start_time,
+            end_time: row.end_time,
+            duration: row.duration,
+            buffer_before: row.buffer_before,
+            buffer_after: row.buffer_after,
+            room_types: row.room_types,
+            busy_starts: row.busy_starts.unwrap_or_default(),
+            busy_ends: row.busy_ends.unwrap_or_default(),
+            room_ids: row.room_ids,
+            room_busy_ids: row.room_busy_ids.unwrap_or_default(),
+            room_busy_starts: row.room_busy_starts.unwrap_or_default(),
+            room_busy_ends: row.room_busy_ends.unwrap_or_default(),
+        };
+        // Прошедшие дни и уже начавшиеся сегодня слоты не предлагаем
+        let slots: Vec<Interval> = day
+            .free_slots()
+            .into_iter()
+            .filter(|slot| day.date > now.date() || (day.date == now.date() && slot.start > minutes_of(now.time())))
+            .collect();
+        days.entry(day.date).or_default().extend(slots);
+    }
+
+    for slots in days.values_mut() {
+        slots.sort_by_key(|slot| (slot.start, slot.end));
+        slots.dedup();
+    }
+    Ok(days)
+}
+
+/// Рабочие дни фотографа (или хотя бы одного активного фотографа) за период,
+/// без учёта записей. Используется в календаре расписания фотографа.
+pub async fn working_days(pool: &PgPool, photographer_id: i32, from: Date, to: Date) -> Result<HashSet<Date>, sqlx::Error> {
+    let days = sqlx::query_scalar!(
+        r#"SELECT DISTINCT wh.date as "date!"
+         FROM resolved_working_hours($2, $3) wh
+         JOIN photographers p ON wh.photographer_id = p.id
+         WHERE wh.start_time < wh.end_time
+         AND (wh.photographer_id = $1 OR ($1 = -1 AND p.is_active))"#,
+        photographer_id,
+        from,
+        to
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(days.into_iter().collect())
+}
@@ -0,0 +1,289 @@
+use std::error::Error;
+
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, Message}};
+
+use crate::admin::reply;
+use crate::models::Service;
+use crate::session::{UserSession, UserStep, UserType};
+
+// Каталог услуг студии: администратор и фотографы, которым он открыл доступ,
+// добавляют, редактируют, переставляют и убирают услуги в архив.
+// Архивные услуги не предлагаются клиентам, но остаются в старых записях
+
+/// Может ли пользователь управлять каталогом услуг.
+pub async fn can_manage(pool: &PgPool, session: &UserSession) -> Result<bool, sqlx::Error> {
+    match (session.user_type, session.photographer_id) {
+        (UserType::Admin, _) => Ok(true),
+        (UserType::Photographer, Some(photographer_id)) => {
+            let allowed = sqlx::query_scalar!(
+                "SELECT can_manage_catalog FROM photographers WHERE id = $1",
+                photographer_id
+            )
+            .fetch_optional(pool)
+            .await?;
+            Ok(allowed.unwrap_or(false))
+        }
+        _ => Ok(false),
+    }
+}
+
+// Куда вернуть пользователя после редактирования
+fn menu_step(session: &UserSession) -> UserStep {
+    match session.user_type {
+        UserType::Admin => UserStep::AdminMenu,
+        _ => UserStep::PhotographerMainMenu,
+    }
+}
+
+// "Название; стоимость; длительность в минутах; описание"
+fn parse_service(text: &str) -> Option<(String, i32, i32, String)> {
+    let mut parts = text.splitn(4, ';').map(str::trim);
+    let name = parts.next().filter(|n| !n.is_empty())?.to_string();
+    let cost: i32 = parts.next()?.parse().ok()?;
+    let duration: i32 = parts.next()?.parse().ok()?;
+    let comment = parts.next().unwrap_or("").to_string();
+    if cost < 0 || duration <= 0 {
+        return None;
+    }
+    Some((name, cost, duration, comment))
+}
+
+// "15/10" или "15 10" — минуты до и после съёмки
+fn parse_buffers(text: &str) -> Option<(i32, i32)> {
+    let mut parts = text.split(|c: char| c == '/' || c.is_whitespace()).filter(|p| !p.is_empty());
+    let before: i32 = parts.next()?.parse().ok()?;
+    let after: i32 = parts.next()?.parse().ok()?;
+    if before < 0 || after < 0 || parts.next().is_some() {
+        return None;
+    }
+    Some((before, after))
+}
+
+/// Ввод данных услуги на шагах `Catalog*`.
+pub async fn handle_message(bot: &Bot, msg: &Message, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let text = msg.text().unwrap_or("").trim();
+
+    if !can_manage(pool, session).await? {
+        session.step = menu_step(session);
+        bot.send_message(chat_id, "Нет доступа к каталогу услуг").await?;
+        return Ok(());
+    }
+
+    if matches!(session.step, UserStep::CatalogAddService) {
+        let Some((name, cost, duration, comment)) = parse_service(text) else {
+            bot.send_message(chat_id, "Неверный формат. Введите: Название; стоимость; длительность в минутах; описание").await?;
+            return Ok(());
+        };
+        let service_id = sqlx::query_scalar!(
+            "INSERT INTO services (name, cost, duration, comment, sort_order)
+             VALUES ($1, $2, $3, $4, (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM services))
+             RETURNING id",
+            name,
+            cost,
+            duration,
+            comment
+        )
+        .fetch_one(pool)
+        .await?;
+        session.step = menu_step(session);
+        return show_service(bot, chat_id, pool, service_id, None).await;
+    }
+
+    let Some(service_id) = session.edit_target_id else {
+        session.step = menu_step(session);
+        return Ok(());
+    };
+
+    let updated = match session.step {
+        UserStep::CatalogEditName if !text.is_empty() => {
+            sqlx::query!("UPDATE services SET name = $2 WHERE id = $1", service_id, text)
+                .execute(pool)
+                .await?;
+            true
+        }
+        UserStep::CatalogEditPrice => match text.parse::<i32>() {
+            Ok(cost) if cost >= 0 => {
+                sqlx::query!("UPDATE services SET cost = $2 WHERE id = $1", service_id, cost)
+                    .execute(pool)
+                    .await?;
+                true
+            }
+            _ => false,
+        },
+        UserStep::CatalogEditDuration => match text.parse::<i32>() {
+            Ok(duration) if duration > 0 => {
+                sqlx::query!("UPDATE services SET duration = $2 WHERE id = $1", service_id, duration)
+                    .execute(pool)
+                    .await?;
+                true
+            }
+            _ => false,
+        },
+        UserStep::CatalogEditComment => {
+            sqlx::query!("UPDATE services SET comment = $2 WHERE id = $1", service_id, text)
+                .execute(pool)
+                .await?;
+            true
+        }
+        UserStep::CatalogEditBuffers => match parse_buffers(text) {
+            Some((before, after)) => {
+                sqlx::query!(
+                    "UPDATE services SET buffer_before = $2, buffer_after = $3 WHERE id = $1",
+                    service_id,
+                    before,
+                    after
+                )
+                .execute(pool)
+                .await?;
+                true
+            }
+            None => false,
+        },
+        _ => false,
+    };
+
+    if !updated {
+        bot.send_message(chat_id, "Неверное значение, попробуйте ещё раз").await?;
+        return Ok(());
+    }
+
+    session.step = menu_step(session);
+    session.edit_target_id = None;
+    show_service(bot, chat_id, pool, service_id, None).await
+}
+
+/// Кнопки каталога, `data` начинается с "catalog:".
+pub async fn handle_callback(bot: &Bot, msg: &Message, data: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let edit = Some(msg);
+
+    if !can_manage(pool, session).await? {
+        bot.send_message(chat_id, "Нет доступа к каталогу услуг").await?;
+        return Ok(());
+    }
+
+    let parts: Vec<&str> = data.split(':').collect();
+    match parts.as_slice() {
+        ["catalog", "list"] => show_catalog(bot, chat_id, pool, edit).await?,
+        ["catalog", "service", id] => show_service(bot, chat_id, pool, id.parse()?, edit).await?,
+        ["catalog", "add"] => {
+            session.step = UserStep::CatalogAddService;
+            session.edit_target_id = None;
+            bot.send_message(chat_id, "Введите услугу через точку с запятой, например:\nПортретная съёмка; 5000; 60; Час съёмки в студии").await?;
+        }
+        ["catalog", "edit", field, id] => {
+            let (step, prompt) = match *field {
+                "name" => (UserStep::CatalogEditName, "Введите новое название услуги:"),
+                "price" => (UserStep::CatalogEditPrice, "Введите новую стоимость в рублях:"),
+                "duration" => (UserStep::CatalogEditDuration, "Введите длительность съёмки в минутах:"),
+                "comment" => (UserStep::CatalogEditComment, "Введите новое описание услуги:"),
+                "buffers" => (UserStep::CatalogEditBuffers, "Введите минуты на подготовку и уборку через /, например: 15/10"),
+                _ => return Ok(()),
+            };
+            session.step = step;
+            session.edit_target_id = Some(id.parse()?);
+            bot.send_message(chat_id, prompt).await?;
+        }
+        ["catalog", "archive", id] => {
+            let service_id: i32 = id.parse()?;
+            sqlx::query!("UPDATE services SET is_active = NOT is_active WHERE id = $1", service_id)
+                .execute(pool)
+                .await?;
+            show_service(bot, chat_id, pool, service_id, edit).await?;
+        }
+        ["catalog", "move", id, direction] => {
+            let service_id: i32 = id.parse()?;
+            move_service(pool, service_id, *direction == "up").await?;
+            show_service(bot, chat_id, pool, service_id, edit).await?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+pub async fn show_catalog(bot: &Bot, chat_id: ChatId, pool: &PgPool, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let services = sqlx::query_as::<_, Service>("SELECT * FROM services ORDER BY sort_order, id")
+        .fetch_all(pool)
+        .await?;
+
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = services
+        .iter()
+        .map(|s| vec![InlineKeyboardButton::callback(
+            format!("{}{}", s.name, if s.is_active { "" } else { " 🗄" }),
+            format!("catalog:service:{}", s.id),
+        )])
+        .collect();
+    keyboard.push(vec![InlineKeyboardButton::callback("➕ Добавить услугу", "catalog:add")]);
+
+    reply(bot, chat_id, edit, "Каталог услуг (🗄 — в архиве):".to_string(), InlineKeyboardMarkup::new(keyboard)).await
+}
+
+async fn show_service(bot: &Bot, chat_id: ChatId, pool: &PgPool, service_id: i32, edit: Option<&Message>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let service = sqlx::query_as::<_, Service>("SELECT * FROM services WHERE id = $1")
+        .bind(service_id)
+        .fetch_one(pool)
+        .await?;
+
+    let text = format!(
+        "Услуга №{}\n\nНазвание: {}\nСтоимость: {} руб.\nДлительность: {} мин.\nПодготовка/уборка: {}/{} мин.\nОписание: {}\nСтатус: {}",
+        service.id,
+        service.name,
+        service.cost,
+        service.duration,
+        service.buffer_before,
+        service.buffer_after,
+        service.comment.as_deref().unwrap_or(""),
+        if service.is_active { "доступна клиентам" } else { "в архиве" }
+    );
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback("✏️ Название", format!("catalog:edit:name:{}", service.id)),
+            InlineKeyboardButton::callback("💰 Цена", format!("catalog:edit:price:{}", service.id)),
+        ],
+        vec![
+            InlineKeyboardButton::callback("⏱ Длительность", format!("catalog:edit:duration:{}", service.id)),
+            InlineKeyboardButton::callback("🧹 Подготовка", format!("catalog:edit:buffers:{}", service.id)),
+        ],
+        vec![InlineKeyboardButton::callback("📝 Описание", format!("catalog:edit:comment:{}", service.id))],
+        vec![
+            InlineKeyboardButton::callback("⬆️ Выше", format!("catalog:move:{}:up", service.id)),
+            InlineKeyboardButton::callback("⬇️ Ниже", format!("catalog:move:{}:down", service.id)),
+        ],
+        vec![InlineKeyboardButton::callback(
+            if service.is_active { "🗄 В архив" } else { "♻️ Вернуть из архива" },
+            format!("catalog:archive:{}", service.id),
+        )],
+        vec![InlineKeyboardButton::callback("⟵ К каталогу", "catalog:list")],
+    ]);
+
+    reply(bot, chat_id, edit, text, keyboard).await
+}
+
+// Меняет услугу местами с соседней и заново нумерует порядок
+async fn move_service(pool: &PgPool, service_id: i32, up: bool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let mut ids = sqlx::query_scalar!("SELECT id FROM services ORDER BY sort_order, id FOR UPDATE")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    if let Some(index) = ids.iter().position(|id| *id == service_id) {
+        match (up, index) {
+            (true, index) if index > 0 => ids.swap(index, index - 1),
+            (false, index) if index + 1 < ids.len() => ids.swap(index, index + 1),
+            _ => return Ok(()),
+        }
+    }
+
+    for (position, id) in ids.iter().enumerate() {
+        sqlx::query!("UPDATE services SET sort_order = $2 WHERE id = $1", id, position as i32 + 1)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
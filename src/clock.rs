@@ -0,0 +1,18 @@
+use chrono::{Datelike, Timelike, Utc};
+use time::{Date, Month, PrimitiveDateTime, Time};
+
+use crate::config;
+
+// Время записей — местное время студии (STUDIO_TIMEZONE). Отсюда берутся
+// «сегодня» для календаря и «сейчас» для отсечения прошедших слотов
+
+pub fn now() -> PrimitiveDateTime {
+    let now = Utc::now().with_timezone(&config::get().studio_timezone);
+    let date = Date::from_calendar_date(now.year(), Month::try_from(now.month() as u8).unwrap(), now.day() as u8).unwrap();
+    let time = Time::from_hms(now.hour() as u8, now.minute() as u8, now.second() as u8).unwrap();
+    PrimitiveDateTime::new(date, time)
+}
+
+pub fn today() -> Date {
+    now().date()
+}
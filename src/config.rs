@@ -0,0 +1,163 @@
+use std::{env, sync::OnceLock};
+
+use chrono_tz::Tz;
+use dotenvy::dotenv;
+
+use crate::assignment::{Balanced, StrategyKind};
+use crate::models::TelegramId;
+
+// Настройки студии из переменных окружения (.env)
+pub struct Config {
+    // Шаг сетки слотов в минутах: 15, 30 или 60
+    pub slot_step_minutes: i32,
+    // Токен платёжного провайдера Telegram Payments; без него оплата отключена
+    pub payment_provider_token: Option<String>,
+    // Доля стоимости услуги, которую нужно внести при записи, 1..=100
+    pub prepayment_percent: i32,
+    pub payment_currency: String,
+    // За сколько часов до съёмки напоминать о ней
+    pub reminder_offsets_hours: Vec<i32>,
+    // Сколько часов фотограф может не подтверждать новую запись; None — без ограничения
+    pub booking_confirmation_hours: Option<i32>,
+    // Telegram ID администраторов студии
+    pub admin_telegram_ids: Vec<TelegramId>,
+    // Сколько минут слот из листа ожидания удерживается за клиентом
+    pub waitlist_hold_minutes: i32,
+    // Часовой пояс студии: в нём показываются записи и считаются «сегодня» и напоминания
+    pub studio_timezone: Tz,
+    // Как выбирать фотографа для записи к «любому фотографу»
+    pub assignment_strategy: StrategyKind,
+    // Веса загрузки и рейтинга для стратегии balanced
+    pub balance_weights: Balanced,
+}
+
+impl Config {
+    fn from_env() -> Self {
+        dotenv().ok();
+
+        let slot_step_minutes = match env::var("SLOT_STEP_MINUTES").ok().and_then(|v| v.parse::<i32>().ok()) {
+            Some(step @ (15 | 30 | 60)) => step,
+            Some(step) => {
+                log::warn!("Unsupported SLOT_STEP_MINUTES={}, falling back to 30", step);
+                30
+            }
+            None => 30,
+        };
+
+        let payment_provider_token = env::var("PAYMENT_PROVIDER_TOKEN").ok().filter(|t| !t.is_empty());
+
+        let prepayment_percent = match env::var("PREPAYMENT_PERCENT").ok().and_then(|v| v.parse::<i32>().ok()) {
+            Some(percent @ 1..=100) => percent,
+            Some(percent) => {
+                log::warn!("Unsupported PREPAYMENT_PERCENT={}, falling back to 100", percent);
+                100
+            }
+            None => 100,
+        };
+
+        let payment_currency = env::var("PAYMENT_CURRENCY").unwrap_or_else(|_| "RUB".to_string());
+
+        // REMINDER_OFFSETS_HOURS=24,2
+        let reminder_offsets_hours = match env::var("REMINDER_OFFSETS_HOURS") {
+            Ok(value) => value
+                .split(',')
+                .filter_map(|v| match v.trim().parse::<i32>() {
+                    Ok(hours) if hours > 0 => Some(hours),
+                    _ => {
+                        log::warn!("Ignoring invalid reminder offset {:?}", v);
+                        None
+                    }
+                })
+                .collect(),
+            Err(_) => vec![24, 2],
+        };
+
+        // BOOKING_CONFIRMATION_HOURS=0 отключает автоотмену
+        let booking_confirmation_hours = match env::var("BOOKING_CONFIRMATION_HOURS").ok().and_then(|v| v.parse::<i32>().ok()) {
+            Some(0) => None,
+            Some(hours) if hours > 0 => Some(hours),
+            Some(hours) => {
+                log::warn!("Unsupported BOOKING_CONFIRMATION_HOURS={}, falling back to 24", hours);
+                Some(24)
+            }
+            None => Some(24),
+        };
+
+        // ADMIN_TELEGRAM_IDS=123456789,987654321
+        let admin_telegram_ids = env::var("ADMIN_TELEGRAM_IDS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|v| !v.trim().is_empty())
+            .filter_map(|v| match v.trim().parse::<i64>() {
+                Ok(id) => Some(TelegramId(id)),
+                Err(_) => {
+                    log::warn!("Ignoring invalid admin telegram id {:?}", v);
+                    None
+                }
+            })
+            .collect();
+
+        let waitlist_hold_minutes = match env::var("WAITLIST_HOLD_MINUTES").ok().and_then(|v| v.parse::<i32>().ok()) {
+            Some(minutes) if minutes > 0 => minutes,
+            Some(minutes) => {
+                log::warn!("Unsupported WAITLIST_HOLD_MINUTES={}, falling back to 30", minutes);
+                30
+            }
+            None => 30,
+        };
+
+        // STUDIO_TIMEZONE=Europe/Moscow, имя из базы IANA. Тот же пояс указан
+        // в миграции, переводящей время записей в TIMESTAMPTZ
+        let studio_timezone = match env::var("STUDIO_TIMEZONE") {
+            Ok(value) => value.parse::<Tz>().unwrap_or_else(|_| {
+                log::warn!("Unsupported STUDIO_TIMEZONE={}, falling back to Europe/Moscow", value);
+                Tz::Europe__Moscow
+            }),
+            Err(_) => Tz::Europe__Moscow,
+        };
+
+        // ASSIGNMENT_STRATEGY=balanced | least_loaded | best_rated | round_robin
+        let assignment_strategy = match env::var("ASSIGNMENT_STRATEGY") {
+            Ok(value) => StrategyKind::parse(value.trim()).unwrap_or_else(|| {
+                log::warn!("Unsupported ASSIGNMENT_STRATEGY={}, falling back to balanced", value);
+                StrategyKind::Balanced
+            }),
+            Err(_) => StrategyKind::Balanced,
+        };
+
+        // BALANCE_WEEK_WEIGHT, BALANCE_MONTH_WEIGHT, BALANCE_RATING_WEIGHT — неотрицательные числа
+        let weight = |name: &str, default: f64| match env::var(name).ok().map(|v| v.trim().parse::<f64>()) {
+            Some(Ok(weight)) if weight >= 0.0 => weight,
+            Some(_) => {
+                log::warn!("Unsupported {}, falling back to {}", name, default);
+                default
+            }
+            None => default,
+        };
+        let balance_weights = Balanced {
+            week_weight: weight("BALANCE_WEEK_WEIGHT", 1.0),
+            month_weight: weight("BALANCE_MONTH_WEIGHT", 0.25),
+            rating_weight: weight("BALANCE_RATING_WEIGHT", 1.0),
+        };
+
+        Config {
+            slot_step_minutes,
+            payment_provider_token,
+            prepayment_percent,
+            payment_currency,
+            reminder_offsets_hours,
+            booking_confirmation_hours,
+            admin_telegram_ids,
+            waitlist_hold_minutes,
+            studio_timezone,
+            assignment_strategy,
+            balance_weights,
+        }
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::from_env)
+}
@@ -1,12 +1,21 @@
-use sqlx::postgres::PgPoolOptions;
+use sqlx::{postgres::PgPoolOptions, Executor};
 use dotenvy::dotenv;
 use std::env;
 
+use crate::config;
+
 pub async fn get_db_pool() -> sqlx::Pool<sqlx::Postgres> {
     dotenv().ok();
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
     PgPoolOptions::new()
+        // Сессия БД работает в часовом поясе студии: DATE(), ::timestamp и
+        // CURRENT_DATE дают местные дату и время
+        .after_connect(|conn, _meta| Box::pin(async move {
+            let timezone = config::get().studio_timezone.name();
+            conn.execute(sqlx::query("SELECT set_config('TimeZone', $1, false)").bind(timezone)).await?;
+            Ok(())
+        }))
         .connect(&database_url)
         .await
         .expect("Failed to connect to DB")
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Local};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
 use log::{error};
 use teloxide::{prelude::*, types::{CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton, KeyboardMarkup, Message, ReplyMarkup, User, WebAppInfo, MessageId}, RequestError};
 use sqlx::{postgres::PgQueryResult, query::{self, Map}, PgPool, Row};
@@ -8,7 +8,21 @@ use std::{collections::HashMap, vec, error::Error};
 use time::{macros::{format_description, time}, Date, Month, PrimitiveDateTime, Time};
 use chrono::Datelike;
 
-use crate::models::{Client, Photographer, Service};
+use crate::models::{BookingStatus, Client, Photographer, Service, TelegramId};
+use crate::session::{UserSession, UserStep, UserType};
+use crate::admin;
+use crate::assignment;
+use crate::availability;
+use crate::catalog;
+use crate::clock;
+use crate::materials;
+use crate::reviews;
+use crate::waitlist;
+use crate::payments;
+use crate::pricing;
+use crate::promo::{self, PromoError};
+use crate::schedule::{self, parse_hours_range};
+use crate::slots::Interval;
 extern crate pretty_env_logger;
 
 #[derive(sqlx::FromRow)]
@@ -16,7 +30,7 @@ struct BookingInfo {
     id: i32,
     booking_start: PrimitiveDateTime,
     booking_end: PrimitiveDateTime,
-    status: String,
+    status: BookingStatus,
     client_name: String,
     service_name: String,
     client_id: Option<i32>,
@@ -25,90 +39,6 @@ struct BookingInfo {
     client_phone: Option<String>,  // Это будет username клиента
 }
 
-// Структура для хранения сессии пользователя
-pub struct UserSession {
-    step: UserStep,
-    client_id: i32,
-    photographer_id: Option<i32>,
-    service_id: Option<i32>,
-    selected_date: Option<Date>,
-    selected_time_start: Option<Time>,
-    selected_time_end: Option<Time>,
-    agreement: bool,
-    user_type: UserType,
-    client_name: String,
-    client_username: String
-}
-
-impl UserSession {
-    fn new() -> Self {
-        UserSession {
-            step: UserStep::Registartion,
-            client_id: -1,
-            photographer_id: None,
-            service_id: None,
-            selected_date: None,
-            selected_time_start: None,
-            selected_time_end: None,
-            agreement: false,
-            user_type: UserType::Unknown,
-            client_name: String::new(),
-            client_username: String::new()
-        }
-    }
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum UserType {
-    Unknown,
-    Client,
-    Photographer
-}
-
-// Перечисление шагов процесса
-#[derive(Debug, Clone, Copy)]
-enum UserStep {
-    Registartion,
-    Start,
-    MainMenu,
-    HistoryOfBookings,
-    SelectingService,
-    SelectingPhotographer,
-    SelectingTime,
-    ConfirmingBooking,
-    Payment,
-    // Photographer specific steps
-    PhotographerMainMenu,
-    ViewSchedule,
-    ViewBookings,
-    ChangeDescription,
-    ChangePortfolio,
-    CustomHours,
-    // New steps
-    ChangeName,
-    PersonalCabinet,
-    SelectTime,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum BookingStatus {
-    New,
-    Confirmed,
-    Completed,
-    Cancelled
-}
-
-impl BookingStatus {
-    fn to_string(&self) -> &'static str {
-        match self {
-            BookingStatus::New => "🆕 Новый",
-            BookingStatus::Confirmed => "✅ Подтвержден",
-            BookingStatus::Completed => "✅ Выполнен",
-            BookingStatus::Cancelled => "❌ Отменен"
-        }
-    }
-}
-
 //todo
 /*
     1. Согласие на обработку данных, 
@@ -117,26 +47,39 @@ impl BookingStatus {
 
 */
 
-pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions: &mut HashMap<i64, UserSession>) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
     let chat_id = msg.chat.id;
     let text = msg.text().unwrap_or_else(|| "");
 
-    let session = user_sessions.entry(chat_id.0).or_insert(UserSession::new());
+    if let Some(payment) = msg.successful_payment() {
+        payments::handle_successful_payment(&bot, &pool, chat_id, payment).await?;
+        session.step = UserStep::MainMenu;
+        return Ok(());
+    }
+
     if let Some(text) = msg.text() {
         match text {
             "/start" => {
+                // Администраторы студии задаются в настройках
+                if admin::is_admin(chat_id.into()) {
+                    session.user_type = UserType::Admin;
+                    session.step = UserStep::AdminMenu;
+                    admin::show_admin_menu(&bot, chat_id).await?;
+                    return Ok(());
+                }
+
                 // Сначала проверяем, является ли пользователь фотографом
-                if let Some(photographer) = check_photographer(&pool, chat_id.0).await {
+                if let Some(photographer) = check_photographer(&pool, chat_id.into()).await {
                     println!("User {} is a photographer", chat_id.0);
                     session.user_type = UserType::Photographer;
                     session.photographer_id = Some(photographer.id);
                     session.step = UserStep::PhotographerMainMenu;
-                    show_photographer_menu(bot.clone(), chat_id).await?;
+                    show_photographer_menu(bot.clone(), chat_id, &pool, photographer.id).await?;
                     return Ok(());
                 }
 
                 // Если не фотограф, проверяем, зарегистрирован ли как клиент
-                if let Some(client) = check_client(&pool, chat_id.0).await {
+                if let Some(client) = check_client(&pool, chat_id.into()).await {
                     println!("User {} is a client", chat_id.0);
                     session.user_type = UserType::Client;
                     session.client_id = client.id;
@@ -212,12 +155,12 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
                 chat_id.0, name, username);
             
             // Проверяем, не является ли пользователь фотографом
-            if let Some(photographer) = check_photographer(&pool, chat_id.0).await {
+            if let Some(photographer) = check_photographer(&pool, chat_id.into()).await {
                 println!("User {} is already a photographer", chat_id.0);
                 session.user_type = UserType::Photographer;
                 session.photographer_id = Some(photographer.id);
                 session.step = UserStep::PhotographerMainMenu;
-                show_photographer_menu(bot.clone(), chat_id).await?;
+                show_photographer_menu(bot.clone(), chat_id, &pool, photographer.id).await?;
                 return Ok(());
             }
             
@@ -244,16 +187,16 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
             println!("Start : {}", text);
             println!("{}, {}", chat_id.0, msg.chat.id);
             // Check if user is a photographer
-            if let Some(photographer) = check_photographer(&pool, chat_id.0).await {
+            if let Some(photographer) = check_photographer(&pool, chat_id.into()).await {
                 session.user_type = UserType::Photographer;
                 session.photographer_id = Some(photographer.id);
                 session.step = UserStep::PhotographerMainMenu;
-                show_photographer_menu(bot.clone(), chat_id).await?;
+                show_photographer_menu(bot.clone(), chat_id, &pool, photographer.id).await?;
                 return Ok(());
             }
 
             // If not a photographer, proceed with client flow
-            let client = check_client(&pool, chat_id.0).await;
+            let client = check_client(&pool, chat_id.into()).await;
             let buttons: Vec<Vec<KeyboardButton>> = vec![
                 vec![KeyboardButton::new("Выбрать услугу")],
                 vec![KeyboardButton::new("Личный кабинет")],
@@ -277,6 +220,28 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
             session.step = UserStep::Registartion;
         }
 
+        UserStep::AdminMenu
+        | UserStep::AdminAddPhotographer
+        | UserStep::AdminEditPhotographer
+        | UserStep::AdminFindBooking
+        | UserStep::AdminAddPriceRule
+        | UserStep::AdminAddPromoCode => {
+            if session.user_type != UserType::Admin {
+                bot.send_message(chat_id, "Неизвестная команда").await?;
+                return Ok(());
+            }
+            admin::handle_message(&bot, &msg, &pool, session).await?;
+        }
+
+        UserStep::CatalogAddService
+        | UserStep::CatalogEditName
+        | UserStep::CatalogEditPrice
+        | UserStep::CatalogEditDuration
+        | UserStep::CatalogEditComment
+        | UserStep::CatalogEditBuffers => {
+            catalog::handle_message(&bot, &msg, &pool, session).await?;
+        }
+
         UserStep::PhotographerMainMenu => {
             if session.user_type != UserType::Photographer {
                 bot.send_message(chat_id, "Неизвестная команда")
@@ -292,6 +257,20 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
                 if let Some(photographer_id) = session.photographer_id {
                     show_photographer_bookings(bot.clone(), chat_id, &pool, photographer_id).await?;
                 }
+            } else if text == "Шаблон расписания" {
+                if let Some(photographer_id) = session.photographer_id {
+                    show_schedule_template(bot.clone(), chat_id, &pool, photographer_id).await?;
+                }
+            } else if text == "Мои услуги" {
+                if let Some(photographer_id) = session.photographer_id {
+                    show_my_services(bot.clone(), chat_id, &pool, photographer_id, None).await?;
+                }
+            } else if text == "Каталог услуг" {
+                if catalog::can_manage(&pool, session).await? {
+                    catalog::show_catalog(&bot, chat_id, &pool, None).await?;
+                } else {
+                    bot.send_message(chat_id, "Нет доступа к каталогу услуг").await?;
+                }
             } else if text == "Изменить портфолио" {
                 session.step = UserStep::ChangePortfolio;
                 bot.send_message(chat_id, "Пришлите новую ссылку на портфолио в виде \"https://www.google.com/\"")
@@ -390,8 +369,26 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
         }
 
         UserStep::ConfirmingBooking => {
-            println!("ConfirmingBooking: {}", text);
-
+            // Клиент вводит промокод к оформляемой записи
+            let Some(service_id) = session.service_id else {
+                session.step = UserStep::MainMenu;
+                return Ok(());
+            };
+            let checked = match promo::find(&pool, text).await? {
+                Some(promo_code) => promo_code.check(service_id).map(|_| promo_code),
+                None => Err(PromoError::NotFound),
+            };
+            match checked {
+                Ok(promo_code) => {
+                    session.promo_code_id = Some(promo_code.id);
+                    session.step = UserStep::SelectingTime;
+                    show_booking_summary(&bot, chat_id, &pool, session, None).await?;
+                }
+                Err(e) => {
+                    bot.send_message(chat_id, format!("{}. Введите другой промокод или нажмите «Подтвердить» в сообщении выше.", e.message()))
+                        .await?;
+                }
+            }
         }
 
         UserStep::HistoryOfBookings => {
@@ -407,7 +404,9 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
         }
 
         UserStep::Payment => {
-            println!("Payment: {}", text);
+            bot.send_message(chat_id, "Оплатите счёт выше, чтобы завершить запись. Если оплатить сейчас не получается, запись сохранится — оплату можно внести в студии.")
+                .await?;
+            session.step = UserStep::MainMenu;
         },
         UserStep::ViewSchedule => {
             if let Some(photographer_id) = session.photographer_id {
@@ -421,41 +420,95 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
         },
         UserStep::CustomHours => {
             if let Some(text) = msg.text() {
-                let parts: Vec<&str> = text.split('-').collect();
-                if parts.len() == 2 {
-                    let start_parts: Vec<&str> = parts[0].split(':').collect();
-                    let end_parts: Vec<&str> = parts[1].split(':').collect();
-                    
-                    if start_parts.len() == 2 && end_parts.len() == 2 {
-                        if let (Ok(start_hour), Ok(end_hour)) = (start_parts[0].parse::<i32>(), end_parts[0].parse::<i32>()) {
-                            if let Some(date) = session.selected_date {
-                                if let Err(e) = save_working_hours(&pool, session.photographer_id.unwrap(), date, start_hour, end_hour).await {
-                                    error!("Error saving working hours: {}", e);
-                                    bot.send_message(chat_id, "Произошла ошибка при сохранении рабочих часов")
-                                        .await
-                                        .unwrap();
-                                } else {
-                                    bot.send_message(chat_id, "Рабочие часы успешно сохранены")
-                                        .await
-                                        .unwrap();
-                                    session.step = UserStep::PhotographerMainMenu;
-                                    show_photographer_menu(bot.clone(), chat_id).await?;
-                                }
-                            }
-                        }
+                if let (Some((start_time, end_time)), Some(date)) = (parse_hours_range(text), session.selected_date) {
+                    if let Err(e) = save_working_hours(&pool, session.photographer_id.unwrap(), date, start_time, end_time).await {
+                        error!("Error saving working hours: {}", e);
+                        bot.send_message(chat_id, "Произошла ошибка при сохранении рабочих часов")
+                            .await
+                            .unwrap();
+                    } else {
+                        bot.send_message(chat_id, "Рабочие часы успешно сохранены")
+                            .await
+                            .unwrap();
+                        offer_freed_slots(&bot, &pool).await;
+                        session.step = UserStep::PhotographerMainMenu;
+                        show_photographer_menu(bot.clone(), chat_id, &pool, session.photographer_id.unwrap()).await?;
                     }
+                    return Ok(());
                 }
-                bot.send_message(chat_id, "Неверный формат. Используйте формат ЧЧ:ЧЧ-ЧЧ:ЧЧ (например, 9:00-18:00)")
+                bot.send_message(chat_id, "Неверный формат. Используйте формат ЧЧ:ММ-ЧЧ:ММ (например, 9:30-18:00)")
                     .await
                     .unwrap();
             }
         },
+        UserStep::ReviewComment => {
+            reviews::handle_comment(&bot, chat_id, text, &pool, session).await?;
+        },
+        UserStep::UploadMaterials => {
+            materials::handle_upload(&bot, &msg, &pool, session).await?;
+        },
+        UserStep::MyServicePrice | UserStep::MyServiceDuration => {
+            if session.user_type != UserType::Photographer {
+                bot.send_message(chat_id, "Неизвестная команда").await?;
+                return Ok(());
+            }
+            let (Some(photographer_id), Some(service_id)) = (session.photographer_id, session.edit_target_id) else {
+                session.step = UserStep::PhotographerMainMenu;
+                return Ok(());
+            };
+            let value = match text.trim().parse::<i32>() {
+                Ok(value) if value > 0 => value,
+                _ => {
+                    bot.send_message(chat_id, "Введите положительное число").await?;
+                    return Ok(());
+                }
+            };
+            if matches!(session.step, UserStep::MyServicePrice) {
+                sqlx::query!(
+                    "UPDATE photographer_services SET price_override = $3 WHERE photographer_id = $1 AND service_id = $2",
+                    photographer_id,
+                    service_id,
+                    value
+                )
+                .execute(&pool)
+                .await?;
+            } else {
+                sqlx::query!(
+                    "UPDATE photographer_services SET duration_override = $3 WHERE photographer_id = $1 AND service_id = $2",
+                    photographer_id,
+                    service_id,
+                    value
+                )
+                .execute(&pool)
+                .await?;
+            }
+            session.step = UserStep::PhotographerMainMenu;
+            session.edit_target_id = None;
+            show_my_service(bot.clone(), chat_id, &pool, photographer_id, service_id, None).await?;
+        },
+        UserStep::EditScheduleTemplate => {
+            let today = clock::today();
+            match schedule::parse_template(text, today) {
+                Some(template) => {
+                    schedule::save_template(&pool, session.photographer_id.unwrap(), &template).await?;
+                    bot.send_message(chat_id, format!("Шаблон расписания сохранён: {}", template.describe())).await?;
+                    offer_freed_slots(&bot, &pool).await;
+                    session.step = UserStep::PhotographerMainMenu;
+                    show_photographer_menu(bot.clone(), chat_id, &pool, session.photographer_id.unwrap()).await?;
+                }
+                None => {
+                    bot.send_message(chat_id, "Не удалось разобрать шаблон. Примеры: \"Пн-Пт 9:00-18:00\", \"Пн,Ср,Пт 10:00-20:00\", \"2/2 10:00-20:00\", \"3/1 с 01.06.2025 10:00-20:00\"").await?;
+                }
+            }
+        },
         UserStep::PersonalCabinet => {
             match text {
                 "История записей" => {
                     let bookings = sqlx::query!(
                         r#"
-                        SELECT b.*, p.name as photographer_name, s.name as service_name
+                        SELECT b.id, b.booking_start::timestamp as "booking_start!", b.booking_end::timestamp as "booking_end!", b.status as "status: BookingStatus",
+                               b.price, p.name as photographer_name, s.name as service_name,
+                               EXISTS(SELECT 1 FROM materials m WHERE m.booking_id = b.id) as "has_materials!"
                         FROM bookings b
                         JOIN photographers p ON b.photographer_id = p.id
                         JOIN services s ON b.service_id = s.id
@@ -489,24 +542,25 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
                             let start_time = booking.booking_start.format(&time_format).unwrap();
                             let end_time = booking.booking_end.format(&time_format).unwrap();
                             
-                            let status = match booking.status.as_str() {
-                                "new" => "🆕 Новый",
-                                "confirmed" => "✅ Подтвержден",
-                                "completed" => "✅ Выполнен",
-                                "cancelled" => "❌ Отменен",
-                                _ => booking.status.as_str()
-                            };
+                            let status = booking.status.to_string();
                             
                             message.push_str(&format!(
-                                "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Фотограф:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
+                                "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Фотограф:* {}\n*Услуга:* {}\n*Стоимость:* {} руб.\n*Статус:* {}\n\n",
                                 booking.id,
                                 date,
                                 start_time,
                                 end_time,
                                 booking.photographer_name,
                                 booking.service_name,
+                                booking.price,
                                 status
                             ));
+                            if booking.has_materials {
+                                keyboard.push(vec![InlineKeyboardButton::callback(
+                                    format!("🖼 Фото по записи №{}", booking.id),
+                                    format!("materials:{}", booking.id)
+                                )]);
+                            }
                         }
 
                         if bookings.len() > bookings_per_page {
@@ -584,7 +638,7 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
             sqlx::query!(
                 "UPDATE clients SET name = $1 WHERE telegram_id = $2",
                 new_name,
-                chat_id.0 as i32
+                TelegramId::from(chat_id) as _
             )
             .execute(&pool)
             .await?;
@@ -604,11 +658,7 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
         UserStep::SelectTime => {
             if let Some(date_str) = text.split('_').nth(1) {
                 if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-                    let today = Date::from_calendar_date(
-                        Local::now().year(),
-                        Month::try_from(Local::now().month() as u8).unwrap(),
-                        Local::now().day() as u8
-                    ).unwrap();
+                    let today = clock::today();
                     let selected_date = Date::from_calendar_date(
                         date.year(),
                         Month::try_from(date.month() as u8).unwrap(),
@@ -633,11 +683,10 @@ pub async fn handle_message(msg: Message, bot: Bot, pool: PgPool, user_sessions:
     Ok(())
 }
 
-pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, pool: PgPool, user_sessions: &mut HashMap<i64, UserSession>) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, pool: PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
     println!("Callback query: {:?}", q.data.clone().unwrap());
     let chat_id: ChatId = msg.chat.id;
     let _text: &str = msg.text().unwrap_or_else(|| "");
-    let session: &mut UserSession = user_sessions.entry(chat_id.0).or_insert(UserSession::new());
 
     if let Some(data) = q.data.clone() {
         match data.as_str() {
@@ -650,9 +699,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -699,13 +748,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         let start_time = booking.booking_start.format(&time_format).unwrap();
                         let end_time = booking.booking_end.format(&time_format).unwrap();
                         
-                        let status = match booking.status.as_str() {
-                            "new" => "🆕 Новый",
-                            "confirmed" => "✅ Подтвержден",
-                            "completed" => "✅ Выполнен",
-                            "cancelled" => "❌ Отменен",
-                            _ => booking.status.as_str()
-                        };
+                        let status = booking.status.to_string();
                         
                         message.push_str(&format!(
                             "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Клиент:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
@@ -742,7 +785,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
 
 
                         // Добавляем кнопки в зависимости от статуса записи
-                        if booking.status == "confirmed" {
+                        if booking.status == BookingStatus::Confirmed {
                             booking_buttons.push(InlineKeyboardButton::callback(
                                 "✅ Завершить".to_string(),
                                 format!("complete_booking:{}", booking.id)
@@ -786,9 +829,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -832,13 +875,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         let start_time = booking.booking_start.format(&time_format).unwrap();
                         let end_time = booking.booking_end.format(&time_format).unwrap();
                         
-                        let status = match booking.status.as_str() {
-                            "new" => "🆕 Новый",
-                            "confirmed" => "✅ Подтвержден",
-                            "completed" => "✅ Выполнен",
-                            "cancelled" => "❌ Отменен",
-                            _ => booking.status.as_str()
-                        };
+                        let status = booking.status.to_string();
                         
                         message.push_str(&format!(
                             "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Клиент:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
@@ -872,6 +909,12 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                 }
                             }
                         }
+                        if booking.status == BookingStatus::Completed {
+                            booking_buttons.push(InlineKeyboardButton::callback(
+                                "📎 Фото".to_string(),
+                                format!("materials_upload:{}", booking.id)
+                            ));
+                        }
                         keyboard.push(booking_buttons);
                     }
 
@@ -907,20 +950,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 let username = session.client_username.clone();
 
                 // Сохраняем данные клиента
-                sqlx::query("INSERT INTO clients (telegram_id, name, username) VALUES ($1, $2, $3) ON CONFLICT (telegram_id) DO UPDATE SET name = $2, username = $3")
-                    .bind(chat_id.0 as i32)
-                    .bind(name)
-                    .bind(username)
-                    .execute(&pool)
-                    .await
-                    .unwrap();
-                
-                // Получаем ID клиента
-                session.client_id = sqlx::query_scalar("SELECT id FROM clients WHERE telegram_id = $1")
-                    .bind(chat_id.0 as i32)
-                    .fetch_one(&pool)
-                    .await
-                    .unwrap();
+                session.client_id = register_client(&pool, chat_id.into(), &name, &username).await?;
                 
                 let buttons: Vec<Vec<KeyboardButton>> = vec![
                     vec![KeyboardButton::new("Выбрать услугу")],
@@ -939,11 +969,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         ["calendar", "select", date] => {
                             let date_format = format_description!("[year]-[month]-[day]");
                             if let Ok(selected_date) = Date::parse(date, &date_format) {
-                                let today = Date::from_calendar_date(
-                                    Local::now().year(),
-                                    Month::try_from(Local::now().month() as u8).unwrap(),
-                                    Local::now().day() as u8
-                                ).unwrap();
+                                let today = clock::today();
                                 
                                 if selected_date < today {
                                     bot.send_message(chat_id, "Нельзя выбрать дату в прошлом. Пожалуйста, выберите другую дату.")
@@ -955,12 +981,11 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                 
                                 if session.user_type == UserType::Photographer {
                                     // Для фотографов показываем настройку рабочего времени
-                                    if let Some((start_hour, end_hour)) = get_working_hours(&pool, session.photographer_id.unwrap(), selected_date).await {
+                                    if let Some((start_time, end_time)) = get_working_hours(&pool, session.photographer_id.unwrap(), selected_date).await {
                                         let message = format!(
-                                            "Текущие рабочие часы на {}: {}:00-{}:00\n\nВыберите новые рабочие часы:",
+                                            "Текущие рабочие часы на {}: {}\n\nВыберите новые рабочие часы:",
                                             selected_date,
-                                            start_hour,
-                                            end_hour
+                                            Interval::from_times(start_time, end_time).label()
                                         );
                                         add_working_day(bot.clone(), chat_id, &pool, session.photographer_id.unwrap(), selected_date).await?;
                                     } else {
@@ -971,13 +996,14 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                     if let Some(service_id) = session.service_id {
                                         if let Some(photographer_id) = session.photographer_id {
                                             // Если выбран конкретный фотограф
-                                            if let Some((start_hour, end_hour)) = get_working_hours(&pool, photographer_id, selected_date).await {
-                                                if start_hour > 0 && end_hour > 0 {
+                                            if let Some((start_time, end_time)) = get_working_hours(&pool, photographer_id, selected_date).await {
+                                                if start_time < end_time {
                                                     let date_time = PrimitiveDateTime::new(selected_date, time!(0:00));
                                                     match get_free_slots(&pool, photographer_id, service_id, date_time).await {
                                                         Ok(slots) => {
                                                             if slots.is_empty() {
                                                                 bot.edit_message_text(chat_id, msg.id, "На выбранную дату нет свободных слотов")
+                                                                    .reply_markup(waitlist::join_keyboard())
                                                                     .await?;
                                                             } else {
                                                                 show_time_slots(bot.clone(), chat_id, slots, msg.id).await?;
@@ -991,10 +1017,12 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                                     }
                                                 } else {
                                                     bot.send_message(chat_id, "На выбранную дату фотограф не работает. Пожалуйста, выберите другую дату.")
+                                                        .reply_markup(waitlist::join_keyboard())
                                                         .await?;
                                                 }
                                             } else {
                                                 bot.send_message(chat_id, "На выбранную дату фотограф не работает. Пожалуйста, выберите другую дату.")
+                                                    .reply_markup(waitlist::join_keyboard())
                                                     .await?;
                                             }
                                         } else {
@@ -1004,6 +1032,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                                 Ok(slots) => {
                                                     if slots.is_empty() {
                                                         bot.edit_message_text(chat_id, msg.id, "На выбранную дату нет свободных слотов")
+                                                            .reply_markup(waitlist::join_keyboard())
                                                             .await?;
                                                     } else {
                                                         show_time_slots(bot.clone(), chat_id, slots, msg.id).await?;
@@ -1029,7 +1058,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         } else {
                             month += 1;
                         }
-                            let new_calendar = generate_calendar(month, year, &pool, session.photographer_id.unwrap(), session.user_type).await;
+                            let new_calendar = generate_calendar(month, year, &pool, session.photographer_id.unwrap_or(-1), calendar_service_id(session), session.user_type).await;
 
                         if let Some(msg) = q.message.clone() {
                             bot.edit_message_reply_markup(msg.chat().id, msg.id())
@@ -1047,7 +1076,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                             month -= 1;
                         }
 
-                            let new_calendar = generate_calendar(month, year, &pool, session.photographer_id.unwrap(), session.user_type).await;
+                            let new_calendar = generate_calendar(month, year, &pool, session.photographer_id.unwrap_or(-1), calendar_service_id(session), session.user_type).await;
 
                         if let Some(msg) = q.message.clone() {
                             bot.edit_message_reply_markup(msg.chat().id, msg.id())
@@ -1069,9 +1098,11 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 let mut message = format!(
                     "Информация о фотографе\n\n\
                     Имя: {}\n\
+                    {}\n\n\
                     {}\n\n",
                     photographer.name,
-                    photographer.description.unwrap_or_else(|| "Нет описания".to_string())
+                    photographer.description.unwrap_or_else(|| "Нет описания".to_string()),
+                    reviews::photographer_summary(&pool, photographer_id).await?
                 );
                 println!("wtf");
 
@@ -1108,80 +1139,28 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                             session.selected_time_start = Some(Time::parse(start, &format).unwrap());
                             session.selected_time_end = Some(Time::parse(end, &format).unwrap());
 
-                            let time: String = format!("{}:{:02}-{}:{:02}", 
-                                session.selected_time_start.unwrap().hour(), 
-                                session.selected_time_start.unwrap().minute(), 
-                                session.selected_time_end.unwrap().hour(), 
-                                session.selected_time_end.unwrap().minute()
-                            );
-                            
-                        let service = sqlx::query_as::<_, Service>(
-                            "SELECT * FROM services WHERE id = $1"
-                        )
-                        .bind(session.service_id.unwrap())
-                        .fetch_one(&pool)
-                            .await?;
-
-                            // Если выбран "любой фотограф", находим свободного фотографа
-                            let photographer = if session.photographer_id.is_none() {
-                                let date_time = PrimitiveDateTime::new(session.selected_date.unwrap(), session.selected_time_start.unwrap());
-                                match find_available_photographer(&pool, session.service_id.unwrap(), date_time).await {
-                                    Ok(Some(photographer)) => photographer,
-                                    Ok(None) => {
-                                        if let Some(msg) = q.message.clone() {
-                                            bot.edit_message_text(chat_id, msg.id(), "К сожалению, на выбранное время нет свободных фотографов. Пожалуйста, выберите другое время.").await?;
-                                        }
-                                        return Ok(());
-                                    },
-                                    Err(e) => {
-                                        error!("Error finding available photographer: {}", e);
-                                        if let Some(msg) = q.message.clone() {
-                                            bot.edit_message_text(chat_id, msg.id(), "Произошла ошибка при поиске фотографа. Пожалуйста, попробуйте позже.").await?;
-                                        }
-                                        return Ok(());
-                                    }
-                                }
-                            } else {
-                                sqlx::query_as::<_, Photographer>(
-                            "SELECT * FROM photographers WHERE id = $1",
-                        )
-                        .bind(session.photographer_id.unwrap())
-                        .fetch_one(&pool)
-                                .await?
-                            };
-
-                        let confirm_button: Vec<String> = vec!["Подтвердить".to_string(), "Изменить".to_string()];
-                        let confirm_action: Vec<String> = vec!["yes".to_string(), "no".to_string()];
-                            let key: InlineKeyboardMarkup = generate_inline_markup("confirming", confirm_button, confirm_action);
-                            
-                            let order_string = format!(
-                                "*Ваша запись:*\r\n\
-                                *Услуга:* {}\r\n\
-                                *Фотограф:* {}\r\n\
-                                *Дата:* {} {} {}\r\n\
-                                *Время:* {}\r\n\
-                                *Стоимость:* {} *рублей*\r\n\
-                                *Адрес:* {}\r\n",
-                                                            service.name,
-                                                            photographer.name,
-                                                            session.selected_date.unwrap().day(), month_name_from_month(session.selected_date.unwrap().month()), session.selected_date.unwrap().year(),
-                                                            time,
-                                                            service.cost,
-                                "Москва, ул. Адмирала, д.4"
-                            );
-                            if let Some(msg) = q.message.clone() {
-                                bot.edit_message_text(chat_id, msg.id(), order_string)
-                                    .parse_mode(teloxide::types::ParseMode::Markdown)
-                                    .reply_markup(key)
-                                    .await?;
-                            }
+                            show_booking_summary(&bot, chat_id, &pool, session, q.message.as_ref().map(|m| m.id())).await?;
                     },
                     _ => {}
                 }
             },
             _ if data.starts_with("service:") => {
                 let service_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
+
+                // Кнопка могла остаться в старом сообщении после переноса услуги в архив
+                let is_active = sqlx::query_scalar!("SELECT is_active FROM services WHERE id = $1", service_id)
+                    .fetch_optional(&pool)
+                    .await?
+                    .unwrap_or(false);
+                if !is_active {
+                    bot.send_message(chat_id, "Эта услуга больше недоступна. Пожалуйста, выберите другую.").await?;
+                    show_services(bot.clone(), chat_id, &pool).await;
+                    return Ok(());
+                }
+
                 session.service_id = Some(service_id);
+                session.reschedule_booking_id = None;
+                session.promo_code_id = None;
                 show_photographers_for_service(bot.clone(), chat_id, &pool, service_id, msg.clone()).await;
             },
             _ if data.starts_with("photographer:") => {
@@ -1194,9 +1173,8 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 }
                 
                 if let Some(msg) = q.message.clone() {
-                    let today_month = chrono::Utc::now().month();
-                    let today_year = chrono::Utc::now().year();
-                    let key = generate_calendar(today_month, today_year, &pool, session.photographer_id.unwrap_or(-1), UserType::Client).await;
+                    let today = clock::today();
+                    let key = generate_calendar(today.month() as u32, today.year(), &pool, session.photographer_id.unwrap_or(-1), session.service_id, UserType::Client).await;
                     
                     bot.edit_message_text(chat_id, msg.id(), "Выбери дату:")
                         .reply_markup(key)
@@ -1205,39 +1183,112 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
             },
             _ if data.starts_with("confirming:") => {
             let answer = data.split(":").collect::<Vec<&str>>();
-            if answer[1] == "yes" {
+            if answer[1] == "promo" {
+                session.step = UserStep::ConfirmingBooking;
+                bot.send_message(chat_id, "Введите промокод:").await?;
+            } else if answer[1] == "yes" {
                 let booking_start = PrimitiveDateTime::new(session.selected_date.unwrap(), session.selected_time_start.unwrap());
                 let booking_end = PrimitiveDateTime::new(session.selected_date.unwrap(), session.selected_time_end.unwrap());
 
-                // Если выбран "любой фотограф", находим свободного фотографа
-                let photographer_id = if session.photographer_id.is_none() {
-                    match find_available_photographer(&pool, session.service_id.unwrap(), booking_start).await {
-                        Ok(Some(photographer)) => photographer.id,
-                        Ok(None) => {
-                            bot.send_message(chat_id, "К сожалению, на выбранное время нет свободных фотографов. Пожалуйста, выберите другое время.").await?;
-                            return Ok(());
-                        },
+                if let Some(booking_id) = session.reschedule_booking_id {
+                    let old_start = sqlx::query_scalar!(
+                        r#"SELECT booking_start::timestamp as "booking_start!" FROM bookings WHERE id = $1"#,
+                        booking_id
+                    )
+                    .fetch_one(&pool)
+                    .await?;
+
+                    match move_booking(&pool, booking_id, session.client_id, booking_start, booking_end).await {
+                        Ok(()) => {
+                            session.reschedule_booking_id = None;
+                            session.step = UserStep::MainMenu;
+                            bot.edit_message_text(chat_id, msg.id, format!("Запись №{} перенесена! Ожидайте подтверждения фотографа.", booking_id))
+                                .await?;
+                            if let Err(e) = notify_photographer_about_reschedule(&bot, &pool, booking_id, old_start).await {
+                                error!("Failed to notify photographer about reschedule: {}", e);
+                            }
+                        }
+                        Err(BookingError::SlotTaken) | Err(BookingError::OutsideWorkingHours) => {
+                            let date_time = PrimitiveDateTime::new(session.selected_date.unwrap(), time!(0:00));
+                            let slots = get_free_slots(&pool, session.photographer_id.unwrap(), session.service_id.unwrap(), date_time).await?;
+                            if slots.is_empty() {
+                                bot.edit_message_text(chat_id, msg.id, "😔 Это время только что заняли, и на выбранную дату больше нет свободных слотов. Пожалуйста, выберите другую дату.")
+                                    .await?;
+                            } else {
+                                show_time_slots(bot.clone(), chat_id, slots, msg.id).await?;
+                                bot.send_message(chat_id, "😔 Это время только что заняли. Пожалуйста, выберите другое время выше.")
+                                    .await?;
+                            }
+                        }
+                        Err(BookingError::NotActive) => {
+                            session.reschedule_booking_id = None;
+                            bot.edit_message_text(chat_id, msg.id, format!("Запись №{} уже нельзя перенести", booking_id))
+                                .await?;
+                        }
                         Err(e) => {
-                            error!("Error finding available photographer: {}", e);
-                            bot.send_message(chat_id, "Произошла ошибка при поиске фотографа. Пожалуйста, попробуйте позже.").await?;
-                            return Ok(());
+                            error!("Error moving booking {}: {}", booking_id, e);
+                            bot.send_message(chat_id, "Ошибка при переносе записи. Попробуйте еще раз.").await?;
                         }
                     }
-                } else {
-                    session.photographer_id.unwrap()
-                };
+                    return Ok(());
+                }
 
+                // Для "любого фотографа" он назначается при создании записи
                 match create_booking(
                     &pool,
                     session.client_id,
-                    photographer_id,
+                    session.photographer_id,
                     session.service_id.unwrap(),
                     booking_start,
-                    booking_end
+                    booking_end,
+                    session.promo_code_id
                 ).await {
-                    Ok(_) => {
-                        bot.edit_message_text(chat_id, msg.id, "Запись оформлена! Ожидайте подтверждения фотографа.")
-                        .await?;
+                    Ok((booking_id, photographer_id)) => {
+                        session.promo_code_id = None;
+                        if let Err(e) = waitlist::mark_booked(&pool, session.client_id, photographer_id, booking_start).await {
+                            error!("Failed to update waitlist for booking {}: {}", booking_id, e);
+                        }
+                        match payments::send_booking_invoice(&bot, &pool, chat_id, booking_id).await {
+                            Ok(true) => {
+                                bot.edit_message_text(chat_id, msg.id, "Запись оформлена! Оплатите счёт ниже, после этого фотограф подтвердит запись.")
+                                    .await?;
+                                session.step = UserStep::Payment;
+                                return Ok(());
+                            }
+                            Ok(false) => {
+                                bot.edit_message_text(chat_id, msg.id, "Запись оформлена! Ожидайте подтверждения фотографа.")
+                                    .await?;
+                            }
+                            Err(e) => {
+                                error!("Error sending invoice for booking {}: {}", booking_id, e);
+                                bot.edit_message_text(chat_id, msg.id, "Запись оформлена! Выставить счёт не удалось, оплату можно внести в студии. Ожидайте подтверждения фотографа.")
+                                    .await?;
+                            }
+                        }
+                    }
+                    Err(BookingError::SlotTaken) | Err(BookingError::OutsideWorkingHours) => {
+                        // Пока клиент подтверждал, время заняли — показываем актуальные слоты на ту же дату
+                        let date_time = PrimitiveDateTime::new(session.selected_date.unwrap(), time!(0:00));
+                        let slots = match session.photographer_id {
+                            Some(photographer_id) => get_free_slots(&pool, photographer_id, session.service_id.unwrap(), date_time).await?,
+                            None => get_available_photographers(&pool, session.service_id.unwrap(), date_time).await?,
+                        };
+                        if slots.is_empty() {
+                            bot.edit_message_text(chat_id, msg.id, "😔 Это время только что заняли, и на выбранную дату больше нет свободных слотов. Пожалуйста, выберите другую дату.")
+                                .await?;
+                        } else {
+                            show_time_slots(bot.clone(), chat_id, slots, msg.id).await?;
+                            bot.send_message(chat_id, "😔 Это время только что заняли. Пожалуйста, выберите другое время выше.")
+                                .await?;
+                        }
+                        return Ok(());
+                    }
+                    Err(BookingError::PromoUnavailable(e)) => {
+                        // Лимит исчерпали или срок истёк, пока клиент подтверждал
+                        session.promo_code_id = None;
+                        bot.send_message(chat_id, format!("{}. Стоимость пересчитана без промокода.", e.message())).await?;
+                        show_booking_summary(&bot, chat_id, &pool, session, Some(msg.id)).await?;
+                        return Ok(());
                     }
                     Err(e) => {
                         error!("Error creating booking: {}", e);
@@ -1255,40 +1306,84 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 }
                 session.step = UserStep::MainMenu;
             } else {
+                session.reschedule_booking_id = None;
+                session.promo_code_id = None;
                 show_services(bot.clone(), chat_id, &pool).await;
             }
         },
             _ if data.starts_with("working_hours:") => {
                 let parts: Vec<&str> = data.split(':').collect();
                 if parts.len() == 3 {
-                    let start_hour = parts[1].parse::<i32>().unwrap();
-                    let end_hour = parts[2].parse::<i32>().unwrap();
+                    let start_hour = parts[1].parse::<u8>().unwrap();
+                    let end_hour = parts[2].parse::<u8>().unwrap();
+                    let start_time = Time::from_hms(start_hour, 0, 0).unwrap();
+                    let end_time = Time::from_hms(end_hour, 0, 0).unwrap();
                     
                     if let Some(date) = session.selected_date {
-                        if let Err(e) = save_working_hours(&pool, session.photographer_id.unwrap(), date, start_hour, end_hour).await {
+                        if let Err(e) = save_working_hours(&pool, session.photographer_id.unwrap(), date, start_time, end_time).await {
                             error!("Error saving working hours: {}", e);
                             bot.send_message(chat_id, "Произошла ошибка при сохранении рабочих часов").await?;
                         } else {
                             bot.send_message(chat_id, "Рабочие часы успешно сохранены").await?;
+                            offer_freed_slots(&bot, &pool).await;
                             show_photographer_schedule(bot.clone(), &msg, &pool, session.photographer_id.unwrap()).await?;
                         }
                     }
                 }
             },
+            _ if data.starts_with("template:") => {
+                let photographer_id = session.photographer_id.unwrap();
+                let today = clock::today();
+                let preset = |kind: schedule::TemplateKind| schedule::ScheduleTemplate {
+                    kind,
+                    start_time: time!(10:00),
+                    end_time: time!(20:00),
+                };
+                let template = match data.as_str() {
+                    "template:5_2" => Some(schedule::ScheduleTemplate {
+                        kind: schedule::TemplateKind::Weekly { weekdays: vec![1, 2, 3, 4, 5] },
+                        start_time: time!(9:00),
+                        end_time: time!(18:00),
+                    }),
+                    "template:2_2" => Some(preset(schedule::TemplateKind::Rotation { days_on: 2, days_off: 2, anchor: today })),
+                    "template:3_1" => Some(preset(schedule::TemplateKind::Rotation { days_on: 3, days_off: 1, anchor: today })),
+                    _ => None,
+                };
+
+                match (data.as_str(), template) {
+                    (_, Some(template)) => {
+                        schedule::save_template(&pool, photographer_id, &template).await?;
+                        bot.edit_message_text(chat_id, msg.id, format!("Шаблон расписания сохранён: {}", template.describe())).await?;
+                        offer_freed_slots(&bot, &pool).await;
+                    }
+                    ("template:custom", None) => {
+                        session.step = UserStep::EditScheduleTemplate;
+                        bot.send_message(chat_id, "Введите шаблон одним сообщением, например:\n\
+                            Пн-Пт 9:00-18:00 — по дням недели\n\
+                            2/2 10:00-20:00 — два через два, начиная с сегодня\n\
+                            3/1 с 01.06.2025 10:00-20:00 — смены с указанной даты").await?;
+                    }
+                    ("template:delete", None) => {
+                        schedule::delete_template(&pool, photographer_id).await?;
+                        bot.edit_message_text(chat_id, msg.id, "Шаблон расписания удалён. Рабочие дни задаются только вручную.").await?;
+                    }
+                    _ => {}
+                }
+            },
             "custom_hours" => {
                 session.step = UserStep::CustomHours;
-                bot.send_message(chat_id, "Введите рабочие часы в формате ЧЧ:ЧЧ-ЧЧ:ЧЧ (например, 9:00-18:00)").await?;
+                bot.send_message(chat_id, "Введите рабочие часы в формате ЧЧ:ММ-ЧЧ:ММ (например, 9:30-18:00)").await?;
             },
             "edit_schedule" => {
-                let today = time::OffsetDateTime::now_utc();
-                let calendar = generate_calendar(today.month() as u32, today.year(), &pool, session.photographer_id.unwrap(), UserType::Photographer).await;
+                let today = clock::today();
+                let calendar = generate_calendar(today.month() as u32, today.year(), &pool, session.photographer_id.unwrap(), None, UserType::Photographer).await;
                 bot.send_message(chat_id, "Выберите дату для редактирования:")
                     .reply_markup(ReplyMarkup::InlineKeyboard(calendar))
                     .await?;
             },
             "add_working_day" => {
-                let today = time::OffsetDateTime::now_utc();
-                let calendar = generate_calendar(today.month() as u32, today.year(), &pool, session.photographer_id.unwrap(), UserType::Photographer).await;
+                let today = clock::today();
+                let calendar = generate_calendar(today.month() as u32, today.year(), &pool, session.photographer_id.unwrap(), None, UserType::Photographer).await;
                 bot.send_message(chat_id, "Выберите дату для добавления рабочего дня:")
                     .reply_markup(ReplyMarkup::InlineKeyboard(calendar))
                     .await?;
@@ -1296,12 +1391,14 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
             _ if data.starts_with("confirm_booking:") => {
                 let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
                 
-                sqlx::query!(
-                    "UPDATE bookings SET status = 'confirmed' WHERE id = $1",
-                    booking_id
-                )
-                .execute(&pool)
-                .await?;
+                match transition_booking_status(&pool, booking_id, BookingStatus::Confirmed).await {
+                    Ok(_) => {}
+                    Err(StatusError::IllegalTransition { from, .. }) => {
+                        bot.send_message(chat_id, format!("Нельзя подтвердить запись №{}: текущий статус {}", booking_id, from)).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
 
                 // Уведомляем клиента
                 if let Some(booking) = sqlx::query!(
@@ -1311,12 +1408,12 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 .fetch_optional(&pool)
                 .await? {
                     if let Some(client) = sqlx::query!(
-                        "SELECT telegram_id FROM clients WHERE id = $1",
+                        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM clients WHERE id = $1"#,
                         booking.client_id
                     )
                     .fetch_optional(&pool)
                     .await? {
-                        bot.send_message(ChatId(client.telegram_id), "Ваша запись была подтверждена фотографом! 🎉").await?;
+                        bot.send_message(ChatId::from(client.telegram_id), "Ваша запись была подтверждена фотографом! 🎉").await?;
                     }
                 }
 
@@ -1324,16 +1421,159 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                     bot.send_message(chat_id, "✅ Запись подтверждена").await?;
                 }
             },
-            _ if data.starts_with("client_reject_booking:") => {
+            _ if data.starts_with("reminder_confirm:") => {
                 let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
-                
-                sqlx::query!(
-                    "UPDATE bookings SET status = 'cancelled' WHERE id = $1",
+
+                let booking = sqlx::query!(
+                    "UPDATE bookings SET client_confirmed_at = CURRENT_TIMESTAMP
+                     WHERE id = $1 AND status = 'confirmed'
+                     RETURNING photographer_id",
                     booking_id
                 )
-                .execute(&pool)
+                .fetch_optional(&pool)
+                .await?;
+
+                match booking {
+                    Some(booking) => {
+                        bot.edit_message_reply_markup(chat_id, msg.id).await?;
+                        bot.send_message(chat_id, "Спасибо! Ждём вас на съёмке 📸").await?;
+
+                        // Уведомляем фотографа
+                        if let Some(photographer) = sqlx::query!(
+                            r#"SELECT telegram_id as "telegram_id: TelegramId" FROM photographers WHERE id = $1"#,
+                            booking.photographer_id
+                        )
+                        .fetch_optional(&pool)
+                        .await? {
+                            if let Some(telegram_id) = photographer.telegram_id {
+                                let text = format!("👍 Клиент подтвердил, что придёт на запись №{}", booking_id);
+                                bot.send_message(ChatId::from(telegram_id), text).await?;
+                            }
+                        }
+                    }
+                    None => {
+                        bot.send_message(chat_id, format!("Запись №{} уже неактивна", booking_id)).await?;
+                    }
+                }
+            },
+            _ if data.starts_with("my_services:") => {
+                let Some(photographer_id) = session.photographer_id else {
+                    return Ok(());
+                };
+                let parts: Vec<&str> = data.split(':').collect();
+                match parts.as_slice() {
+                    ["my_services", "list"] => {
+                        show_my_services(bot.clone(), chat_id, &pool, photographer_id, Some(msg.id)).await?;
+                    }
+                    ["my_services", "toggle", service_id] => {
+                        let service_id = service_id.parse::<i32>()?;
+                        let removed = sqlx::query!(
+                            "DELETE FROM photographer_services WHERE photographer_id = $1 AND service_id = $2",
+                            photographer_id,
+                            service_id
+                        )
+                        .execute(&pool)
+                        .await?
+                        .rows_affected();
+                        if removed == 0 {
+                            sqlx::query!(
+                                "INSERT INTO photographer_services (photographer_id, service_id) VALUES ($1, $2)
+                                 ON CONFLICT DO NOTHING",
+                                photographer_id,
+                                service_id
+                            )
+                            .execute(&pool)
+                            .await?;
+                        }
+                        show_my_services(bot.clone(), chat_id, &pool, photographer_id, Some(msg.id)).await?;
+                    }
+                    ["my_services", "service", service_id] => {
+                        show_my_service(bot.clone(), chat_id, &pool, photographer_id, service_id.parse()?, Some(msg.id)).await?;
+                    }
+                    ["my_services", "price", service_id] => {
+                        session.step = UserStep::MyServicePrice;
+                        session.edit_target_id = Some(service_id.parse()?);
+                        bot.send_message(chat_id, "Введите вашу цену за услугу в рублях:").await?;
+                    }
+                    ["my_services", "duration", service_id] => {
+                        session.step = UserStep::MyServiceDuration;
+                        session.edit_target_id = Some(service_id.parse()?);
+                        bot.send_message(chat_id, "Введите вашу длительность съёмки в минутах:").await?;
+                    }
+                    ["my_services", "reset", service_id] => {
+                        let service_id = service_id.parse::<i32>()?;
+                        sqlx::query!(
+                            "UPDATE photographer_services SET price_override = NULL, duration_override = NULL
+                             WHERE photographer_id = $1 AND service_id = $2",
+                            photographer_id,
+                            service_id
+                        )
+                        .execute(&pool)
+                        .await?;
+                        show_my_service(bot.clone(), chat_id, &pool, photographer_id, service_id, Some(msg.id)).await?;
+                    }
+                    _ => {}
+                }
+            },
+            _ if data.starts_with("waitlist:") => {
+                waitlist::handle_callback(&bot, &msg, &data, &pool, session).await?;
+            },
+            _ if data.starts_with("rate:") || data.starts_with("review_skip:") => {
+                reviews::handle_callback(&bot, chat_id, &data, &pool, session).await?;
+            },
+            _ if data.starts_with("materials") => {
+                materials::handle_callback(&bot, chat_id, &data, &pool, session).await?;
+            },
+            _ if data.starts_with("catalog:") => {
+                catalog::handle_callback(&bot, &msg, &data, &pool, session).await?;
+            },
+            _ if data.starts_with("admin:") && session.user_type == UserType::Admin => {
+                admin::handle_callback(&bot, &msg, &data, &pool, session).await?;
+            },
+            _ if data.starts_with("reschedule:") => {
+                let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
+
+                let booking = sqlx::query!(
+                    r#"SELECT photographer_id as "photographer_id!", service_id as "service_id!", status as "status: BookingStatus"
+                     FROM bookings WHERE id = $1 AND client_id = $2"#,
+                    booking_id,
+                    session.client_id
+                )
+                .fetch_optional(&pool)
                 .await?;
 
+                match booking {
+                    Some(booking) if booking.status == BookingStatus::New || booking.status == BookingStatus::Confirmed => {
+                        // Дальше клиент проходит обычный выбор даты и времени у того же фотографа и на ту же услугу
+                        session.reschedule_booking_id = Some(booking_id);
+                        session.photographer_id = Some(booking.photographer_id);
+                        session.service_id = Some(booking.service_id);
+                        session.promo_code_id = None;
+                        session.step = UserStep::SelectingTime;
+
+                        let today = clock::today();
+                        let key = generate_calendar(today.month() as u32, today.year(), &pool, booking.photographer_id, session.service_id, UserType::Client).await;
+                        bot.send_message(chat_id, format!("Выберите новую дату для записи №{}:", booking_id))
+                            .reply_markup(key)
+                            .await?;
+                    }
+                    _ => {
+                        bot.send_message(chat_id, format!("Запись №{} уже нельзя перенести", booking_id)).await?;
+                    }
+                }
+            },
+            _ if data.starts_with("client_reject_booking:") => {
+                let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
+                
+                match transition_booking_status(&pool, booking_id, BookingStatus::Cancelled).await {
+                    Ok(_) => {}
+                    Err(StatusError::IllegalTransition { from, .. }) => {
+                        bot.send_message(chat_id, format!("Нельзя отменить запись №{}: текущий статус {}", booking_id, from)).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
                 // Уведомляем фотографа
                 if let Some(booking) = sqlx::query!(
                     "SELECT photographer_id, id FROM bookings WHERE id = $1",
@@ -1342,15 +1582,16 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 .fetch_optional(&pool)
                 .await? {
                     if let Some(photographer) = sqlx::query!(
-                        "SELECT telegram_id FROM photographers WHERE id = $1",
+                        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM photographers WHERE id = $1"#,
                         booking.photographer_id
                     )
                     .fetch_optional(&pool)
                     .await? {
                         let text = format!("К сожалению, клиент отменил запись №{} к вам 😔", booking.id);
-                        bot.send_message(ChatId(photographer.telegram_id.unwrap()), text).await?;
+                        bot.send_message(ChatId::from(photographer.telegram_id.unwrap()), text).await?;
                     }
                 }
+                offer_freed_slots(&bot, &pool).await;
 
                 if let Some(msg) = q.message.clone() {
                     let text = format!("❌ Запись №{} отменена", booking_id);
@@ -1360,12 +1601,14 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
             _ if data.starts_with("reject_booking:") => {
                 let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
                 
-                sqlx::query!(
-                    "UPDATE bookings SET status = 'cancelled' WHERE id = $1",
-                    booking_id
-                )
-                .execute(&pool)
-                .await?;
+                match transition_booking_status(&pool, booking_id, BookingStatus::Cancelled).await {
+                    Ok(_) => {}
+                    Err(StatusError::IllegalTransition { from, .. }) => {
+                        bot.send_message(chat_id, format!("Нельзя отменить запись №{}: текущий статус {}", booking_id, from)).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
 
                 // Уведомляем клиента
                 if let Some(booking) = sqlx::query!(
@@ -1375,14 +1618,15 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 .fetch_optional(&pool)
                 .await? {
                     if let Some(client) = sqlx::query!(
-                        "SELECT telegram_id FROM clients WHERE id = $1",
+                        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM clients WHERE id = $1"#,
                         booking.client_id
                     )
                     .fetch_optional(&pool)
                     .await? {
-                        bot.send_message(ChatId(client.telegram_id), "К сожалению, фотограф отклонил вашу запись 😔").await?;
+                        bot.send_message(ChatId::from(client.telegram_id), "К сожалению, фотограф отклонил вашу запись 😔").await?;
                     }
                 }
+                offer_freed_slots(&bot, &pool).await;
 
                 if let Some(msg) = q.message.clone() {
                     let text = format!("❌ Запись №{} отменена", booking_id);
@@ -1397,9 +1641,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -1443,13 +1687,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         let start_time = booking.booking_start.format(&time_format).unwrap();
                         let end_time = booking.booking_end.format(&time_format).unwrap();
                         
-                        let status = match booking.status.as_str() {
-                            "new" => "🆕 Новый",
-                            "confirmed" => "✅ Подтвержден",
-                            "completed" => "✅ Выполнен",
-                            "cancelled" => "❌ Отменен",
-                            _ => booking.status.as_str()
-                        };
+                        let status = booking.status.to_string();
                         
                         message.push_str(&format!(
                             "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Клиент:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
@@ -1498,9 +1736,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -1542,13 +1780,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         let start_time = booking.booking_start.format(&time_format).unwrap();
                         let end_time = booking.booking_end.format(&time_format).unwrap();
                         
-                        let status = match booking.status.as_str() {
-                            "new" => "🆕 Новый",
-                            "confirmed" => "✅ Подтвержден",
-                            "completed" => "✅ Выполнен",
-                            "cancelled" => "❌ Отменен",
-                            _ => booking.status.as_str()
-                        };
+                        let status = booking.status.to_string();
                         
                         message.push_str(&format!(
                             "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Клиент:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
@@ -1582,6 +1814,12 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                 }
                             }
                         }
+                        if booking.status == BookingStatus::Completed {
+                            booking_buttons.push(InlineKeyboardButton::callback(
+                                "📎 Фото".to_string(),
+                                format!("materials_upload:{}", booking.id)
+                            ));
+                        }
                         keyboard.push(booking_buttons);
                     }
 
@@ -1639,9 +1877,8 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
             },
             _ if data == "back_to_calendar" => {
                 if let (Some(photographer_id), Some(service_id)) = (session.photographer_id, session.service_id) {
-    let today_month = chrono::Utc::now().month();
-                    let today_year = chrono::Utc::now().year();
-                    let key = generate_calendar(today_month, today_year, &pool, photographer_id, UserType::Client).await;
+                    let today = clock::today();
+                    let key = generate_calendar(today.month() as u32, today.year(), &pool, photographer_id, Some(service_id), UserType::Client).await;
                     bot.send_message(chat_id, "Выбери дату:")
                         .reply_markup(ReplyMarkup::InlineKeyboard(key))
                         .await?;
@@ -1696,9 +1933,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -1766,7 +2003,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         ];
 
                         // Добавляем кнопки в зависимости от статуса записи
-                        if booking.status == "new" {
+                        if booking.status == BookingStatus::New {
                             booking_buttons.push(InlineKeyboardButton::callback(
                                 "✅ Подтвердить".to_string(),
                                 format!("confirm_booking:{}", booking.id)
@@ -1830,9 +2067,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -1894,7 +2131,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         ];
 
                         // Добавляем кнопки в зависимости от статуса записи
-                        if booking.status == "new" {
+                        if booking.status == BookingStatus::New {
                             booking_buttons.push(InlineKeyboardButton::callback(
                                 "✅ Подтвердить".to_string(),
                                 format!("confirm_booking:{}", booking.id)
@@ -1956,9 +2193,9 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         r#"
                         SELECT 
                             b.id,
-                            b.booking_start,
-                            b.booking_end,
-                            b.status,
+                            b.booking_start::timestamp as "booking_start!",
+                            b.booking_end::timestamp as "booking_end!",
+                            b.status as "status: BookingStatus",
                             c.name as client_name,
                             s.name as service_name,
                             b.client_id,
@@ -2000,13 +2237,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                         let start_time = booking.booking_start.format(&time_format).unwrap();
                         let end_time = booking.booking_end.format(&time_format).unwrap();
                         
-                        let status = match booking.status.as_str() {
-                            "new" => "🆕 Новый",
-                            "confirmed" => "✅ Подтвержден",
-                            "completed" => "✅ Выполнен",
-                            "cancelled" => "❌ Отменен",
-                            _ => booking.status.as_str()
-                        };
+                        let status = booking.status.to_string();
                         
                         message.push_str(&format!(
                             "*Запись №{}*\n*Дата:* {}\n*Время:* {} - {}\n*Клиент:* {}\n*Услуга:* {}\n*Статус:* {}\n\n",
@@ -2040,6 +2271,12 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                                 }
                             }
                         }
+                        if booking.status == BookingStatus::Completed {
+                            booking_buttons.push(InlineKeyboardButton::callback(
+                                "📎 Фото".to_string(),
+                                format!("materials_upload:{}", booking.id)
+                            ));
+                        }
                         keyboard.push(booking_buttons);
                     }
 
@@ -2072,8 +2309,8 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                     "confirm" => {
                         // Получаем данные клиента перед архивацией
                         if let Some(client) = sqlx::query!(
-                            "SELECT telegram_id, name, username FROM clients WHERE telegram_id = $1",
-                            chat_id.0 as i32
+                            r#"SELECT telegram_id as "telegram_id: TelegramId", name, username FROM clients WHERE telegram_id = $1"#,
+                            TelegramId::from(chat_id) as _
                         )
                         .fetch_optional(&pool)
                         .await? {
@@ -2081,7 +2318,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                             sqlx::query!(
                                 "INSERT INTO archived_clients (telegram_id, name, username)
                                  VALUES ($1, $2, $3)",
-                                client.telegram_id,
+                                client.telegram_id as _,
                                 client.name,
                                 client.username
                             )
@@ -2091,7 +2328,7 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                             // Удаляем клиента из основной таблицы
                             sqlx::query!(
                                 "DELETE FROM clients WHERE telegram_id = $1",
-                                chat_id.0 as i32
+                                TelegramId::from(chat_id) as _
                             )
                             .execute(&pool)
                             .await?;
@@ -2115,13 +2352,15 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
             _ if data.starts_with("complete_booking:") => {
                 let booking_id = data.split(':').nth(1).unwrap().parse::<i32>().unwrap();
                 
-                sqlx::query!(
-                    "UPDATE bookings SET status = 'выполнено' WHERE id = $1",
-                    booking_id
-                )
-                .execute(&pool)
-                .await?;
-
+                match transition_booking_status(&pool, booking_id, BookingStatus::Completed).await {
+                    Ok(_) => {}
+                    Err(StatusError::IllegalTransition { from, .. }) => {
+                        bot.send_message(chat_id, format!("Нельзя завершить запись №{}: текущий статус {}", booking_id, from)).await?;
+                        return Ok(());
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
                 // Уведомляем клиента
                 if let Some(booking) = sqlx::query!(
                     "SELECT client_id FROM bookings WHERE id = $1",
@@ -2130,18 +2369,25 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
                 .fetch_optional(&pool)
                 .await? {
                     if let Some(client) = sqlx::query!(
-                        "SELECT telegram_id FROM clients WHERE id = $1",
+                        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM clients WHERE id = $1"#,
                         booking.client_id
                     )
                     .fetch_optional(&pool)
                     .await? {
-                        bot.send_message(ChatId(client.telegram_id), "Ваша запись была отмечена как завершенная! 🎉").await?;
+                        bot.send_message(ChatId::from(client.telegram_id), "Ваша запись была отмечена как завершенная! 🎉\n\nОцените, пожалуйста, съёмку:")
+                            .reply_markup(reviews::rating_keyboard(booking_id))
+                            .await?;
                     }
                 }
 
                 if let Some(msg) = q.message.clone() {
                     let text = format!("✅ Запись №{} отмечена как завершенная",booking_id);
-                    bot.send_message(chat_id, text).await?;
+                    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                        InlineKeyboardButton::callback("📎 Прикрепить фото", format!("materials_upload:{}", booking_id)),
+                    ]]);
+                    bot.send_message(chat_id, text)
+                        .reply_markup(keyboard)
+                        .await?;
                 }
             },
             _ => {}
@@ -2151,103 +2397,25 @@ pub async fn handle_callback_query(q: CallbackQuery, bot: Bot, msg: Message, poo
     Ok(())
 }
 
-async fn get_free_slots(
+pub(crate) async fn get_free_slots(
     pool: &PgPool,
     photographer_id: i32,
     service_id: i32,
     date: PrimitiveDateTime,
 ) -> Result<Vec<String>, sqlx::Error> {
-    // 1. Получаем длительность услуги в минутах и конвертируем в часы
-    let duration_minutes: i32 = sqlx::query_scalar!(
-        "SELECT duration FROM services WHERE id = $1",
-        service_id
-    )
-    .fetch_one(pool)
-    .await?;
-
-    let duration_hours = (duration_minutes as f64 / 60.0).ceil() as i32;
-    println!("Duration in minutes: {}, Duration in hours: {}", duration_minutes, duration_hours);
-
-    // 2. Получаем рабочие часы фотографа на эту дату
-    let working_hours = sqlx::query!(
-        "SELECT start_hour, end_hour FROM working_hours
-         WHERE photographer_id = $1 AND date = $2",
-        photographer_id,
-        date.date()
-    )
-    .fetch_optional(pool)
-    .await?;
-
-    println!("Working hours: {:?}", working_hours);
-
-    let (start_hour, end_hour) = match working_hours {
-        Some(hours) => (hours.start_hour, hours.end_hour),
-        None => return Ok(vec![]), // Если нет рабочих часов, возвращаем пустой список
-    };
-
-    println!("Start hour: {}, End hour: {}", start_hour, end_hour);
-
-    // 3. Получаем все бронирования на эту дату
-    let date_offset = date.assume_utc();
-    let bookings = sqlx::query!(
-        "SELECT booking_start, booking_end FROM bookings
-         WHERE photographer_id = $1
-         AND DATE(booking_start) = DATE($2)
-         AND status != 'cancelled'",
-        photographer_id,
-        date_offset
-    )
-    .fetch_all(pool)
-    .await?;
-
-    println!("Bookings count: {}", bookings.len());
-
-    // 4. Строим часовые слоты в пределах рабочих часов
-    let mut free_slots = vec![];
-    
-    
-    // Начинаем с начала рабочего дня
-    let mut current_hour = start_hour;
-    
-    // Продолжаем, пока текущий час + длительность услуги не превысит конец рабочего дня
-    while current_hour + duration_hours <= end_hour {
-        let slot_start = PrimitiveDateTime::new(date.date(), time!(0:00) + time::Duration::hours(current_hour as i64));
-        let slot_end = slot_start + time::Duration::hours(duration_hours as i64);
-
-        // Проверяем, пересекается ли слот с существующими бронированиями
-        let is_slot_free = !bookings.iter().any(|b| {
-            let booking_start = b.booking_start;
-            let booking_end = b.booking_end;
-            
-            // Проверяем пересечение временных интервалов
-            let slot_start_time = slot_start.time();
-            let slot_end_time = slot_end.time();
-            let booking_start_time = booking_start.time();
-            let booking_end_time = booking_end.time();
-            
-            let overlaps = (slot_start_time < booking_end_time) && (slot_end_time > booking_start_time);
-            println!("Slot {}:00-{}:00 overlaps with booking {}:00-{}:00: {}", 
-                current_hour, current_hour + duration_hours,
-                booking_start_time.hour(), booking_end_time.hour(),
-                overlaps);
-            overlaps
-        });
-
-        if is_slot_free {
-            println!("Adding free slot: {}:00-{}:00", current_hour, current_hour + duration_hours);
-            free_slots.push(format!("{:02}:00-{:02}:00", current_hour, current_hour + duration_hours));
-        }
-
-        current_hour += 1;
-    }
-
-    println!("Total free slots: {}", free_slots.len());
-    Ok(free_slots)
+    // Тот же расчёт, что и в календаре, только за один день
+    let mut days = availability::free_slots(pool, photographer_id, service_id, date.date(), date.date()).await?;
+    Ok(days
+        .remove(&date.date())
+        .unwrap_or_default()
+        .iter()
+        .map(Interval::label)
+        .collect())
 }
 
 // Функции для работы с БД
 async fn get_services(pool: &PgPool) -> Vec<Service> {
-    sqlx::query_as::<_, Service>("SELECT * FROM services")
+    sqlx::query_as::<_, Service>("SELECT * FROM services WHERE is_active ORDER BY sort_order, id")
         .fetch_all(pool)
         .await
         .unwrap()
@@ -2280,7 +2448,11 @@ async fn get_photographers_by_service(pool: &PgPool, service_id: i32) -> Vec<Pho
     sqlx::query_as::<_, Photographer>(
         "SELECT p.* FROM photographers p
          JOIN photographer_services ps ON p.id = ps.photographer_id
-         WHERE ps.service_id = $1"
+         LEFT JOIN (SELECT photographer_id, AVG(rating) AS average FROM reviews GROUP BY photographer_id) r
+             ON r.photographer_id = p.id
+         WHERE ps.service_id = $1
+         AND p.is_active
+         ORDER BY r.average DESC NULLS LAST, p.id"
     )
     .bind(service_id)
     .fetch_all(pool)
@@ -2298,6 +2470,8 @@ async fn show_photographers_for_service(bot: Bot, chat_id: ChatId, pool: &PgPool
         return;
     }
 
+    let ratings = reviews::ratings(pool).await.unwrap_or_default();
+
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = Vec::new();
     
     // Add "Any photographer" button
@@ -2308,8 +2482,12 @@ async fn show_photographers_for_service(bot: Bot, chat_id: ChatId, pool: &PgPool
     
     // Add photographer buttons
     for p in &photographers {
+        let name = match ratings.get(&p.id) {
+            Some(rating) => format!("{} {}", p.name, rating.label()),
+            None => p.name.clone(),
+        };
         keyboard.push(vec![InlineKeyboardButton::callback(
-            p.name.clone(),
+            name,
             format!("photographer:{}", p.id)
         ),
         InlineKeyboardButton::callback(
@@ -2333,6 +2511,127 @@ async fn show_photographers_for_service(bot: Bot, chat_id: ChatId, pool: &PgPool
                                     .await;
 }
 
+// Сводка записи перед подтверждением. `message_id` — сообщение, которое нужно заменить
+pub(crate) async fn show_booking_summary(bot: &Bot, chat_id: ChatId, pool: &PgPool, session: &mut UserSession, message_id: Option<MessageId>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let reply_text = |text: &'static str| async move {
+        match message_id {
+            Some(message_id) => bot.edit_message_text(chat_id, message_id, text).await.map(|_| ()),
+            None => bot.send_message(chat_id, text).await.map(|_| ()),
+        }
+    };
+
+    let time: String = format!("{}:{:02}-{}:{:02}",
+        session.selected_time_start.unwrap().hour(),
+        session.selected_time_start.unwrap().minute(),
+        session.selected_time_end.unwrap().hour(),
+        session.selected_time_end.unwrap().minute()
+    );
+
+    let service = sqlx::query_as::<_, Service>(
+        "SELECT * FROM services WHERE id = $1"
+    )
+    .bind(session.service_id.unwrap())
+    .fetch_one(pool)
+    .await?;
+
+    let booking_start = PrimitiveDateTime::new(session.selected_date.unwrap(), session.selected_time_start.unwrap());
+    let booking_end = PrimitiveDateTime::new(session.selected_date.unwrap(), session.selected_time_end.unwrap());
+
+    // Для "любого фотографа" показываем того, кого назначит стратегия студии
+    let photographer_id = match session.photographer_id {
+        Some(photographer_id) => photographer_id,
+        None => match assignment::preview(pool, service.id, booking_start, booking_end).await {
+            Ok(Some(photographer_id)) => photographer_id,
+            Ok(None) => {
+                reply_text("К сожалению, на выбранное время нет свободных фотографов. Пожалуйста, выберите другое время.").await?;
+                return Ok(());
+            },
+            Err(e) => {
+                error!("Error finding available photographer: {}", e);
+                reply_text("Произошла ошибка при поиске фотографа. Пожалуйста, попробуйте позже.").await?;
+                return Ok(());
+            }
+        },
+    };
+    let photographer = sqlx::query_as::<_, Photographer>(
+        "SELECT * FROM photographers WHERE id = $1",
+    )
+    .bind(photographer_id)
+    .fetch_one(pool)
+    .await?;
+    let photographer_name = match session.photographer_id {
+        Some(_) => photographer.name.clone(),
+        None => format!("{} (назначается автоматически)", photographer.name),
+    };
+
+    // Итоговая цена: личная цена фотографа и скидка на выбранное время
+    let quote = pricing::quote(&mut *pool.acquire().await?, photographer.id, service.id, booking_start).await?;
+
+    // Промокод мог истечь, пока клиент выбирал время
+    let promo_code = match session.promo_code_id {
+        Some(promo_code_id) => promo::get(pool, promo_code_id).await?.filter(|p| p.check(service.id).is_ok()),
+        None => None,
+    };
+    session.promo_code_id = promo_code.as_ref().map(|p| p.id);
+
+    let price = match &promo_code {
+        Some(promo_code) => format!(
+            "{}\r\n*Промокод {}:* {}, итого {} руб.",
+            quote.describe(),
+            promo_code.code,
+            promo_code.discount_label(),
+            promo_code.apply(quote.price)
+        ),
+        None => quote.describe(),
+    };
+
+    let (confirm_button, confirm_action): (Vec<String>, Vec<String>) = if session.reschedule_booking_id.is_some() || promo_code.is_some() {
+        (vec!["Подтвердить".to_string(), "Изменить".to_string()], vec!["yes".to_string(), "no".to_string()])
+    } else {
+        (
+            vec!["Подтвердить".to_string(), "🎟 Ввести промокод".to_string(), "Изменить".to_string()],
+            vec!["yes".to_string(), "promo".to_string(), "no".to_string()],
+        )
+    };
+    let key: InlineKeyboardMarkup = generate_inline_markup("confirming", confirm_button, confirm_action);
+
+    let title = match session.reschedule_booking_id {
+        Some(booking_id) => format!("Перенос записи №{}", booking_id),
+        None => "Ваша запись".to_string(),
+    };
+    let order_string = format!(
+        "*{}:*\r\n\
+        *Услуга:* {}\r\n\
+        *Фотограф:* {}\r\n\
+        *Дата:* {} {} {}\r\n\
+        *Время:* {}\r\n\
+        *Стоимость:* {}\r\n\
+        *Адрес:* {}\r\n",
+        title,
+        service.name,
+        photographer_name,
+        session.selected_date.unwrap().day(), month_name_from_month(session.selected_date.unwrap().month()), session.selected_date.unwrap().year(),
+        time,
+        price,
+        "Москва, ул. Адмирала, д.4"
+    );
+    match message_id {
+        Some(message_id) => {
+            bot.edit_message_text(chat_id, message_id, order_string)
+                .parse_mode(teloxide::types::ParseMode::Markdown)
+                .reply_markup(key)
+                .await?;
+        }
+        None => {
+            bot.send_message(chat_id, order_string)
+                .parse_mode(teloxide::types::ParseMode::Markdown)
+                .reply_markup(key)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
 pub fn generate_inline_markup(mark: &str, button: Vec<String>, action: Vec<String>) -> InlineKeyboardMarkup {
     //todo кнопка назад
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = Vec::new();
@@ -2345,7 +2644,15 @@ pub fn generate_inline_markup(mark: &str, button: Vec<String>, action: Vec<Strin
     InlineKeyboardMarkup::new(keyboard)
 }
 
-pub async fn generate_calendar(month: u32, year: i32, pool: &PgPool, photographer_id: i32, user_type: UserType) -> InlineKeyboardMarkup {
+// Услуга, по которой календарь показывает свободные дни; у фотографа — только рабочие дни
+fn calendar_service_id(session: &UserSession) -> Option<i32> {
+    match session.user_type {
+        UserType::Client => session.service_id,
+        _ => None,
+    }
+}
+
+pub async fn generate_calendar(month: u32, year: i32, pool: &PgPool, photographer_id: i32, service_id: Option<i32>, user_type: UserType) -> InlineKeyboardMarkup {
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = Vec::new();
 
     // 1. Заголовок с месяцем и годом
@@ -2370,35 +2677,40 @@ pub async fn generate_calendar(month: u32, year: i32, pool: &PgPool, photographe
             row.push(InlineKeyboardButton::callback(" ".to_string(), "ignore".to_string()));
         }
 
+        // Доступность всего месяца одним запросом: клиенту — число свободных
+        // слотов услуги по дням, фотографу — рабочие дни
+        let first = Date::from_calendar_date(year, Month::try_from(month as u8).unwrap(), 1).unwrap();
+        let last = Date::from_calendar_date(year, Month::try_from(month as u8).unwrap(), num_days as u8).unwrap();
+        let day_loads: HashMap<Date, Option<availability::Load>> = match service_id {
+            Some(service_id) => availability::free_slots(pool, photographer_id, service_id, first, last)
+                .await
+                .map(|days| days.into_iter().map(|(date, slots)| (date, Some(availability::Load::of(slots.len())))).collect()),
+            None => availability::working_days(pool, photographer_id, first, last)
+                .await
+                .map(|days| days.into_iter().map(|date| (date, None)).collect()),
+        }
+        .unwrap_or_else(|e| {
+            error!("Failed to load calendar availability: {}", e);
+            HashMap::new()
+        });
+
+        let today = clock::today();
         for day in 1..=num_days {
             let naive_date = NaiveDate::from_ymd_opt(year, month, day).unwrap();
-            let today = Local::now().date_naive();
             let date = Date::from_calendar_date(year, Month::try_from(month as u8).unwrap(), day as u8).unwrap();
             
-            if naive_date < today {
+            if date < today {
                 // Для дат в прошлом добавляем неактивную кнопку
                 row.push(InlineKeyboardButton::callback(
                     format!("❌ {}", day),
                     "ignore".to_string(),
                 ));
             } else {
-                // Проверяем, является ли день рабочим
-                let is_working_day = if photographer_id == -1 {
-                    // Для "любого фотографа" проверяем наличие хотя бы одного фотографа с рабочими часами
-                    check_any_photographer_available(pool, date).await
-                } else {
-                    if let Some((start_hour, end_hour)) = get_working_hours(pool, photographer_id, date).await {
-                        start_hour > 0 && end_hour > 0
-                    } else {
-                        false
-                    }
-                };
-
                 let callback = format!("calendar:select:{}", naive_date);
-                let button_text = if is_working_day {
-                    format!("{:2}", day) // Просто число для рабочих дней
-                } else {
-                    format!("❌ {:2}", day) // Крестик для нерабочих дней
+                let button_text = match day_loads.get(&date) {
+                    Some(Some(load)) => format!("{}{}", load.mark(), day), // Загруженность дня для клиента
+                    Some(None) => format!("{:2}", day), // Просто число для рабочих дней
+                    None => format!("❌ {:2}", day), // Крестик для нерабочих дней
                 };
                 row.push(InlineKeyboardButton::callback(button_text, callback));
             }
@@ -2417,6 +2729,11 @@ pub async fn generate_calendar(month: u32, year: i32, pool: &PgPool, photographe
         }
     }
 
+    // Пояснение к отметкам загруженности
+    if service_id.is_some() {
+        keyboard.push(vec![InlineKeyboardButton::callback(availability::LEGEND, "ignore".to_string())]);
+    }
+
     // 4. Переключатели месяцев
     keyboard.push(vec![
         InlineKeyboardButton::callback("< Месяц", format!("calendar:prev_month:{}:{}", month, year)),
@@ -2434,85 +2751,265 @@ pub async fn generate_calendar(month: u32, year: i32, pool: &PgPool, photographe
     InlineKeyboardMarkup::new(keyboard)
 }
 
-// Обновляем функцию проверки доступности любого фотографа
-async fn check_any_photographer_available(pool: &PgPool, date: Date) -> bool {
-    // Проверяем, есть ли хотя бы один фотограф с рабочими часами на эту дату
-    let result = sqlx::query!(
-        "SELECT EXISTS (
-            SELECT 1 FROM working_hours wh
-            JOIN photographers p ON wh.photographer_id = p.id
-            JOIN photographer_services ps ON p.id = ps.photographer_id
-            WHERE wh.date = $1
-            AND wh.start_hour > 0
-            AND wh.end_hour > 0
-        ) as exists",
-        date
-    )
-    .fetch_one(pool)
-    .await;
+// Свободные слоты любого фотографа: объединение слотов всех активных фотографов с этой услугой
+async fn get_available_photographers(pool: &PgPool, service_id: i32, date: PrimitiveDateTime) -> Result<Vec<String>, sqlx::Error> {
+    get_free_slots(pool, -1, service_id, date).await
+}
 
-    match result {
-        Ok(row) => row.exists.unwrap_or(false),
-        Err(_) => false
+// Освободившееся время сразу предлагаем листу ожидания
+async fn offer_freed_slots(bot: &Bot, pool: &PgPool) {
+    if let Err(e) = waitlist::process(bot, pool).await {
+        error!("Error processing waitlist: {}", e);
     }
 }
 
-// Обновляем функцию получения доступных слотов для любого фотографа
-async fn get_available_photographers(pool: &PgPool, service_id: i32, date: PrimitiveDateTime) -> Result<Vec<String>, sqlx::Error> {
-    // Получаем всех фотографов, которые предоставляют данную услугу и имеют рабочие часы на эту дату
-    let photographers = sqlx::query!(
-        "SELECT DISTINCT p.id 
-         FROM photographers p
-         JOIN photographer_services ps ON p.id = ps.photographer_id
-         JOIN working_hours wh ON p.id = wh.photographer_id
-         WHERE ps.service_id = $1
-         AND wh.date = $2
-         AND wh.start_hour > 0
-         AND wh.end_hour > 0",
+// Ошибки создания записи
+#[derive(Debug)]
+enum BookingError {
+    // Слот заняли между показом свободного времени и подтверждением
+    SlotTaken,
+    // Время не попадает в рабочие часы фотографа или уже прошло
+    OutsideWorkingHours,
+    // Запись уже отменена, завершена или принадлежит другому клиенту
+    NotActive,
+    // Промокод перестал действовать
+    PromoUnavailable(PromoError),
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for BookingError {
+    fn from(e: sqlx::Error) -> Self {
+        // 23P01 — нарушение ограничения bookings_no_overlap
+        if let sqlx::Error::Database(db_error) = &e {
+            if db_error.code().as_deref() == Some("23P01") {
+                return BookingError::SlotTaken;
+            }
+        }
+        BookingError::Db(e)
+    }
+}
+
+impl std::fmt::Display for BookingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BookingError::SlotTaken => write!(f, "slot is already taken"),
+            BookingError::OutsideWorkingHours => write!(f, "slot is outside of working hours"),
+            BookingError::NotActive => write!(f, "booking is not active"),
+            BookingError::PromoUnavailable(e) => write!(f, "promo code is unavailable: {:?}", e),
+            BookingError::Db(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl Error for BookingError {}
+
+// Проверяет внутри транзакции, что время свободно, и подбирает зал.
+// Строка фотографа блокируется до конца транзакции, поэтому параллельные
+// записи к нему выполняются по очереди. `exclude_booking_id` — переносимая запись,
+// её текущее время не считается занятым
+async fn reserve_slot(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    photographer_id: i32,
+    service_id: i32,
+    booking_start: PrimitiveDateTime,
+    booking_end: PrimitiveDateTime,
+    exclude_booking_id: Option<i32>,
+) -> Result<Option<i32>, BookingError> {
+    sqlx::query!(
+        "SELECT id FROM photographers WHERE id = $1 FOR UPDATE",
+        photographer_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    // Запись вместе с подготовкой и уборкой студии
+    let service = sqlx::query!(
+        r#"SELECT s.buffer_before, s.buffer_after, s.room_types,
+                  COALESCE(ps.duration_override, s.duration) as "duration!"
+         FROM services s
+         LEFT JOIN photographer_services ps ON ps.service_id = s.id AND ps.photographer_id = $2
+         WHERE s.id = $1"#,
         service_id,
-        date.date()
+        photographer_id
     )
-    .fetch_all(pool)
+    .fetch_one(&mut **tx)
     .await?;
 
-    let mut all_slots = Vec::new();
-    
-    // Для каждого фотографа получаем свободные слоты
-    for photographer in photographers {
-        if let Ok(slots) = get_free_slots(pool, photographer.id, service_id, date).await {
-            all_slots.extend(slots);
+    // У фотографа может быть своя длительность услуги, а слот «любого фотографа»
+    // мог прийти от другого. Время не того размера этому фотографу не подходит
+    if booking_end - booking_start != time::Duration::minutes(service.duration as i64) {
+        return Err(BookingError::SlotTaken);
+    }
+
+    let occupied_start = booking_start - time::Duration::minutes(service.buffer_before as i64);
+    let occupied_end = booking_end + time::Duration::minutes(service.buffer_after as i64);
+
+    // Повторно проверяем рабочие часы на момент записи
+    let working_hours = sqlx::query!(
+        r#"SELECT start_time as "start_time!", end_time as "end_time!"
+         FROM resolved_working_hours($2, $2)
+         WHERE photographer_id = $1"#,
+        photographer_id,
+        booking_start.date()
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+
+    let within_hours = match working_hours {
+        Some(hours) => occupied_start.date() == booking_start.date()
+            && occupied_end.date() == booking_start.date()
+            && occupied_start.time() >= hours.start_time
+            && occupied_end.time() <= hours.end_time,
+        None => false,
+    };
+    // Время, которое уже прошло по часам студии, тоже вне рабочего графика
+    if !within_hours || booking_start <= clock::now() {
+        return Err(BookingError::OutsideWorkingHours);
+    }
+
+    // Повторно проверяем пересечение с активными записями с учётом их буферов
+    let overlaps = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM bookings b
+            JOIN services s ON b.service_id = s.id
+            WHERE b.photographer_id = $1
+            AND b.status != 'cancelled'
+            AND ($4::int IS NULL OR b.id <> $4)
+            AND b.booking_start - make_interval(mins => s.buffer_before) < $3::timestamp
+            AND b.booking_end + make_interval(mins => s.buffer_after) > $2::timestamp
+        ) as "exists!""#,
+        photographer_id,
+        occupied_start,
+        occupied_end,
+        exclude_booking_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    if overlaps {
+        return Err(BookingError::SlotTaken);
+    }
+
+    // Подбираем свободный зал подходящего типа, если он нужен услуге
+    match &service.room_types {
+        Some(room_types) if !room_types.is_empty() => {
+            let room_id = sqlx::query_scalar!(
+                "SELECT r.id FROM rooms r
+                 WHERE r.room_type = ANY($1)
+                 AND r.is_active
+                 AND NOT EXISTS (
+                     SELECT 1 FROM bookings b
+                     JOIN services s ON b.service_id = s.id
+                     WHERE b.room_id = r.id
+                     AND b.status != 'cancelled'
+                     AND ($4::int IS NULL OR b.id <> $4)
+                     AND b.booking_start - make_interval(mins => s.buffer_before) < $3::timestamp
+                     AND b.booking_end + make_interval(mins => s.buffer_after) > $2::timestamp
+                 )
+                 ORDER BY r.id
+                 LIMIT 1
+                 FOR UPDATE OF r",
+                room_types,
+                occupied_start,
+                occupied_end,
+                exclude_booking_id
+            )
+            .fetch_optional(&mut **tx)
+            .await?;
+            match room_id {
+                Some(room_id) => Ok(Some(room_id)),
+                None => Err(BookingError::SlotTaken),
+            }
         }
+        _ => Ok(None),
     }
-    
-    // Удаляем дубликаты слотов и сортируем их
-    all_slots.sort();
-    all_slots.dedup();
-    
-    println!("Found {} available slots for any photographer", all_slots.len());
-    for slot in &all_slots {
-        println!("Available slot: {}", slot);
+}
+
+// Первый по стратегии студии фотограф, у которого время свободно. Всех кандидатов
+// блокируем сразу и по порядку id, чтобы параллельные записи не ждали друг друга по кругу
+async fn assign_photographer(
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    client_id: i32,
+    service_id: i32,
+    booking_start: PrimitiveDateTime,
+    booking_end: PrimitiveDateTime,
+) -> Result<(i32, Option<i32>), BookingError> {
+    sqlx::query!(
+        "SELECT p.id FROM photographers p
+         JOIN photographer_services ps ON ps.photographer_id = p.id
+         WHERE ps.service_id = $1 AND p.is_active
+         ORDER BY p.id
+         FOR UPDATE OF p",
+        service_id
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    for candidate in assignment::candidates(&mut **tx, service_id, booking_start).await? {
+        match reserve_slot(&mut *tx, candidate.photographer_id, service_id, booking_start, booking_end, None).await {
+            Ok(room_id) => {
+                if !waitlist::is_held_by_other(&mut *tx, candidate.photographer_id, client_id, booking_start, booking_end).await? {
+                    return Ok((candidate.photographer_id, room_id));
+                }
+            }
+            Err(BookingError::SlotTaken) | Err(BookingError::OutsideWorkingHours) => {}
+            Err(e) => return Err(e),
+        }
     }
-    
-    Ok(all_slots)
+    Err(BookingError::SlotTaken)
 }
 
-async fn create_booking(pool: &PgPool, client_id: i32, photographer_id: i32, service_id: i32, booking_start: PrimitiveDateTime, booking_end: PrimitiveDateTime) -> Result<i32, sqlx::Error> {
+// Возвращает номер записи и фотографа, которому она досталась
+async fn create_booking(pool: &PgPool, client_id: i32, photographer_id: Option<i32>, service_id: i32, booking_start: PrimitiveDateTime, booking_end: PrimitiveDateTime, promo_code_id: Option<i32>) -> Result<(i32, i32), BookingError> {
+    let mut tx = pool.begin().await?;
+
+    // None — "любой фотограф", назначаем в той же транзакции
+    let (photographer_id, room_id) = match photographer_id {
+        Some(photographer_id) => {
+            let room_id = reserve_slot(&mut tx, photographer_id, service_id, booking_start, booking_end, None).await?;
+            if waitlist::is_held_by_other(&mut tx, photographer_id, client_id, booking_start, booking_end).await? {
+                return Err(BookingError::SlotTaken);
+            }
+            (photographer_id, room_id)
+        }
+        None => assign_photographer(&mut tx, client_id, service_id, booking_start, booking_end).await?,
+    };
+
+    // Цена фиксируется в записи и дальше не меняется вместе с каталогом и скидками
+    let quote = pricing::quote(&mut tx, photographer_id, service_id, booking_start).await?;
+
+    // Промокод проверяем повторно под блокировкой, чтобы не превысить лимит
+    let price = match promo_code_id {
+        Some(promo_code_id) => {
+            let promo_code = promo::lock(&mut tx, promo_code_id)
+                .await?
+                .ok_or(BookingError::PromoUnavailable(PromoError::NotFound))?;
+            promo_code.check(service_id).map_err(BookingError::PromoUnavailable)?;
+            promo_code.apply(quote.price)
+        }
+        None => quote.price,
+    };
+
     let booking_id = sqlx::query_scalar!(
-        "INSERT INTO bookings (client_id, photographer_id, service_id, booking_start, booking_end, status)
-         VALUES ($1, $2, $3, $4, $5, 'new')
+        "INSERT INTO bookings (client_id, photographer_id, service_id, booking_start, booking_end, status, room_id, price, promo_code_id)
+         VALUES ($1, $2, $3, $4::timestamp, $5::timestamp, 'new', $6, $7, $8)
          RETURNING id",
         client_id,
         photographer_id,
         service_id,
         booking_start,
-        booking_end
+        booking_end,
+        room_id,
+        price,
+        promo_code_id
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     // Отправляем уведомление фотографу
     if let Some(photographer) = sqlx::query!(
-        "SELECT telegram_id FROM photographers WHERE id = $1",
+        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM photographers WHERE id = $1"#,
         photographer_id
     )
     .fetch_optional(pool)
@@ -2525,9 +3022,9 @@ async fn create_booking(pool: &PgPool, client_id: i32, photographer_id: i32, ser
                 r#"
                 SELECT 
                     b.id,
-                    b.booking_start,
-                    b.booking_end,
-                    b.status,
+                    b.booking_start::timestamp as "booking_start!",
+                    b.booking_end::timestamp as "booking_end!",
+                    b.status as "status: BookingStatus",
                     c.name as client_name,
                     s.name as service_name,
                     b.client_id,
@@ -2561,8 +3058,7 @@ async fn create_booking(pool: &PgPool, client_id: i32, photographer_id: i32, ser
             );
             println!("Sending notification to photographer with telegram_id: {}", telegram_id);
 
-            // Отправляем уведомление фотографу, используя абсолютное значение telegram_id
-            if let Err(e) = bot.send_message(ChatId(telegram_id.abs() as i64), message)
+            if let Err(e) = bot.send_message(ChatId::from(telegram_id), message)
                 .parse_mode(teloxide::types::ParseMode::Markdown)
                 .await {
                     error!("Failed to send notification to photographer: {}", e);
@@ -2571,33 +3067,247 @@ async fn create_booking(pool: &PgPool, client_id: i32, photographer_id: i32, ser
         }
     }
 
-    Ok(booking_id)
+    Ok((booking_id, photographer_id))
 }
 
-async fn check_client (pool: &PgPool, telegram_id: i64) -> Option<Client> {
+// Переносит запись клиента на новое время. Прежнее время сохраняется в booking_history,
+// запись снова ждёт подтверждения фотографа, а напоминания и срок подтверждения
+// отсчитываются заново
+async fn move_booking(pool: &PgPool, booking_id: i32, client_id: i32, booking_start: PrimitiveDateTime, booking_end: PrimitiveDateTime) -> Result<(), BookingError> {
+    let mut tx = pool.begin().await?;
+
+    let booking = sqlx::query!(
+        r#"SELECT photographer_id as "photographer_id!", service_id as "service_id!",
+                  booking_start::timestamp as "booking_start!", booking_end::timestamp as "booking_end!",
+                  room_id, promo_code_id, status as "status: BookingStatus"
+         FROM bookings WHERE id = $1 AND client_id = $2 FOR UPDATE"#,
+        booking_id,
+        client_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(BookingError::NotActive)?;
+
+    match change_booking_status(&mut tx, booking_id, StatusChange::Reschedule).await {
+        Ok(_) => {}
+        Err(StatusError::Db(e)) => return Err(e.into()),
+        Err(_) => return Err(BookingError::NotActive),
+    }
+
+    let room_id = reserve_slot(&mut tx, booking.photographer_id, booking.service_id, booking_start, booking_end, Some(booking_id)).await?;
+    if waitlist::is_held_by_other(&mut tx, booking.photographer_id, client_id, booking_start, booking_end).await? {
+        return Err(BookingError::SlotTaken);
+    }
+
+    // Скидка зависит от времени съёмки, поэтому цену пересчитываем на новое время.
+    // Промокод уже учтён в лимите и сохраняется при переносе
+    let quote = pricing::quote(&mut tx, booking.photographer_id, booking.service_id, booking_start).await?;
+    let price = match booking.promo_code_id {
+        Some(promo_code_id) => match promo::get(pool, promo_code_id).await? {
+            Some(promo_code) => promo_code.apply(quote.price),
+            None => quote.price,
+        },
+        None => quote.price,
+    };
+
+    sqlx::query!(
+        "INSERT INTO booking_history (booking_id, booking_start, booking_end, status, room_id, reason)
+         VALUES ($1, $2::timestamp, $3::timestamp, $4, $5, 'rescheduled')",
+        booking_id,
+        booking.booking_start,
+        booking.booking_end,
+        booking.status as BookingStatus,
+        booking.room_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE bookings
+         SET booking_start = $2::timestamp, booking_end = $3::timestamp, room_id = $4, price = $5,
+             confirmation_requested_at = CURRENT_TIMESTAMP, client_confirmed_at = NULL
+         WHERE id = $1",
+        booking_id,
+        booking_start,
+        booking_end,
+        room_id,
+        price
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM booking_reminders WHERE booking_id = $1", booking_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+// Просим фотографа заново подтвердить перенесённую запись
+async fn notify_photographer_about_reschedule(bot: &Bot, pool: &PgPool, booking_id: i32, old_start: PrimitiveDateTime) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let booking = sqlx::query!(
+        r#"SELECT b.booking_start::timestamp as "booking_start!", b.booking_end::timestamp as "booking_end!", c.name as client_name, s.name as service_name, p.telegram_id as "telegram_id: TelegramId"
+         FROM bookings b
+         JOIN clients c ON b.client_id = c.id
+         JOIN services s ON b.service_id = s.id
+         JOIN photographers p ON b.photographer_id = p.id
+         WHERE b.id = $1"#,
+        booking_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let Some(telegram_id) = booking.telegram_id else {
+        return Ok(());
+    };
+
+    let format = format_description!("[day].[month].[year] [hour]:[minute]");
+    let time_format = format_description!("[hour]:[minute]");
+    let message = format!(
+        "🔁 Клиент {} перенёс запись №{} ({})\n\n\
+        Было: {}\n\
+        Стало: {} - {}\n\n\
+        Подтвердите запись заново.",
+        booking.client_name,
+        booking_id,
+        booking.service_name,
+        old_start.format(&format).unwrap(),
+        booking.booking_start.format(&format).unwrap(),
+        booking.booking_end.format(&time_format).unwrap()
+    );
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("✅ Подтвердить", format!("confirm_booking:{}", booking_id)),
+        InlineKeyboardButton::callback("❌ Отклонить", format!("reject_booking:{}", booking_id)),
+    ]]);
+    bot.send_message(ChatId::from(telegram_id), message)
+        .reply_markup(ReplyMarkup::InlineKeyboard(keyboard))
+        .await?;
+    Ok(())
+}
+
+// Ошибки смены статуса записи
+#[derive(Debug)]
+pub(crate) enum StatusError {
+    NotFound,
+    IllegalTransition { from: BookingStatus, to: BookingStatus },
+    Db(sqlx::Error),
+}
+
+impl From<sqlx::Error> for StatusError {
+    fn from(e: sqlx::Error) -> Self {
+        StatusError::Db(e)
+    }
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusError::NotFound => write!(f, "booking not found"),
+            StatusError::IllegalTransition { from, to } => write!(f, "illegal status transition {:?} -> {:?}", from, to),
+            StatusError::Db(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl Error for StatusError {}
+
+// Смена статуса: обычный переход по таблице или перенос записи
+#[derive(Debug, Clone, Copy)]
+enum StatusChange {
+    To(BookingStatus),
+    Reschedule,
+}
+
+// Возвращает предыдущий статус
+pub(crate) async fn transition_booking_status(pool: &PgPool, booking_id: i32, to: BookingStatus) -> Result<BookingStatus, StatusError> {
+    let mut tx = pool.begin().await?;
+    let from = change_booking_status(&mut tx, booking_id, StatusChange::To(to)).await?;
+    tx.commit().await?;
+    Ok(from)
+}
+
+// Единственное место, где меняется статус записи
+async fn change_booking_status(tx: &mut sqlx::Transaction<'_, sqlx::Postgres>, booking_id: i32, change: StatusChange) -> Result<BookingStatus, StatusError> {
+    let from = sqlx::query_scalar!(
+        r#"SELECT status as "status: BookingStatus" FROM bookings WHERE id = $1 FOR UPDATE"#,
+        booking_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(StatusError::NotFound)?;
+
+    let (allowed, to) = match change {
+        StatusChange::To(to) => (from.can_transition_to(to), to),
+        StatusChange::Reschedule => (from.can_reschedule(), BookingStatus::New),
+    };
+    if !allowed {
+        return Err(StatusError::IllegalTransition { from, to });
+    }
+
+    sqlx::query!(
+        "UPDATE bookings SET status = $1 WHERE id = $2",
+        to as BookingStatus,
+        booking_id
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(from)
+}
+
+// Сохраняет клиента после согласия с правилами и возвращает его id
+async fn register_client(pool: &PgPool, telegram_id: TelegramId, name: &str, username: &str) -> Result<i32, sqlx::Error> {
+    sqlx::query_scalar(
+        "INSERT INTO clients (telegram_id, name, username) VALUES ($1, $2, $3)
+         ON CONFLICT (telegram_id) DO UPDATE SET name = $2, username = $3
+         RETURNING id"
+    )
+    .bind(telegram_id)
+    .bind(name)
+    .bind(username)
+    .fetch_one(pool)
+    .await
+}
+
+async fn check_client (pool: &PgPool, telegram_id: TelegramId) -> Option<Client> {
     sqlx::query_as::<_, Client>("SELECT * FROM clients WHERE telegram_id = $1")
-        .bind(telegram_id as i32)
+        .bind(telegram_id)
         .fetch_optional(pool)
         .await
         .unwrap()
 }
 
-async fn check_photographer(pool: &PgPool, telegram_id: i64) -> Option<Photographer> {
+async fn check_photographer(pool: &PgPool, telegram_id: TelegramId) -> Option<Photographer> {
     sqlx::query_as::<_, Photographer>("SELECT * FROM photographers WHERE telegram_id = $1")
-        .bind(telegram_id as i64)
+        .bind(telegram_id)
         .fetch_optional(pool)
         .await
         .unwrap()
 }
 
-async fn show_photographer_menu(bot: Bot, chat_id: ChatId) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let buttons: Vec<Vec<KeyboardButton>> = vec![
+async fn show_photographer_menu(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut buttons: Vec<Vec<KeyboardButton>> = vec![
         vec![KeyboardButton::new("Моё расписание")],
+        vec![KeyboardButton::new("Шаблон расписания")],
         vec![KeyboardButton::new("Мои записи")],
+        vec![KeyboardButton::new("Мои услуги")],
         vec![KeyboardButton::new("Изменить портфолио")],
         vec![KeyboardButton::new("Изменить свое описание")],
     ];
 
+    // Каталог услуг виден только фотографам, которым администратор открыл доступ
+    let can_manage_catalog = sqlx::query_scalar!(
+        "SELECT can_manage_catalog FROM photographers WHERE id = $1",
+        photographer_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or(false);
+    if can_manage_catalog {
+        buttons.push(vec![KeyboardButton::new("Каталог услуг")]);
+    }
+
     let keyboard = KeyboardMarkup::new(buttons).resize_keyboard();
     bot.send_message(chat_id, "Выбери действие")
         .reply_markup(ReplyMarkup::Keyboard(keyboard))
@@ -2606,9 +3316,112 @@ async fn show_photographer_menu(bot: Bot, chat_id: ChatId) -> Result<(), Box<dyn
     Ok(())
 }
 
+// Услуги каталога, которые фотограф включил у себя
+async fn show_my_services(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32, message_id: Option<MessageId>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let services = sqlx::query!(
+        r#"SELECT s.id, s.name, ps.id as "link_id?"
+         FROM services s
+         LEFT JOIN photographer_services ps ON ps.service_id = s.id AND ps.photographer_id = $1
+         WHERE s.is_active
+         ORDER BY s.sort_order, s.id"#,
+        photographer_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = services
+        .iter()
+        .map(|s| {
+            let mut row = vec![InlineKeyboardButton::callback(
+                format!("{} {}", if s.link_id.is_some() { "✅" } else { "➕" }, s.name),
+                format!("my_services:toggle:{}", s.id),
+            )];
+            if s.link_id.is_some() {
+                row.push(InlineKeyboardButton::callback("⚙️", format!("my_services:service:{}", s.id)));
+            }
+            row
+        })
+        .collect();
+
+    let text = "Ваши услуги. Нажмите на услугу, чтобы включить или выключить её, ⚙️ — личные цена и длительность:";
+    let keyboard = InlineKeyboardMarkup::new(keyboard);
+    match message_id {
+        Some(message_id) => {
+            bot.edit_message_text(chat_id, message_id, text).reply_markup(keyboard).await?;
+        }
+        None => {
+            bot.send_message(chat_id, text).reply_markup(ReplyMarkup::InlineKeyboard(keyboard)).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn show_my_service(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32, service_id: i32, message_id: Option<MessageId>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let service = sqlx::query!(
+        "SELECT s.name, s.cost, s.duration, ps.price_override, ps.duration_override
+         FROM services s
+         JOIN photographer_services ps ON ps.service_id = s.id
+         WHERE ps.photographer_id = $1 AND s.id = $2",
+        photographer_id,
+        service_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let text = format!(
+        "{}\n\n💰 Цена: {} руб.{}\n⏱ Длительность: {} мин.{}",
+        service.name,
+        service.price_override.unwrap_or(service.cost),
+        if service.price_override.is_some() { format!(" (в каталоге {})", service.cost) } else { String::new() },
+        service.duration_override.unwrap_or(service.duration),
+        if service.duration_override.is_some() { format!(" (в каталоге {})", service.duration) } else { String::new() }
+    );
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback("💰 Моя цена", format!("my_services:price:{}", service_id)),
+            InlineKeyboardButton::callback("⏱ Моя длительность", format!("my_services:duration:{}", service_id)),
+        ],
+        vec![InlineKeyboardButton::callback("↩️ Как в каталоге", format!("my_services:reset:{}", service_id))],
+        vec![InlineKeyboardButton::callback("⟵ Назад", "my_services:list")],
+    ]);
+    match message_id {
+        Some(message_id) => {
+            bot.edit_message_text(chat_id, message_id, text).reply_markup(keyboard).await?;
+        }
+        None => {
+            bot.send_message(chat_id, text).reply_markup(ReplyMarkup::InlineKeyboard(keyboard)).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn show_schedule_template(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let message = match schedule::get_template(pool, photographer_id).await? {
+        Some(template) => format!(
+            "Текущий шаблон: {}\n\nИзменения на конкретные даты можно внести в разделе \"Моё расписание\" — они важнее шаблона.",
+            template.describe()
+        ),
+        None => "Шаблон расписания не задан. Выберите готовый вариант или задайте свой:".to_string(),
+    };
+
+    let keyboard = InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("5/2 (Пн–Пт, 9:00-18:00)", "template:5_2")],
+        vec![InlineKeyboardButton::callback("2/2 (10:00-20:00)", "template:2_2")],
+        vec![InlineKeyboardButton::callback("3/1 (10:00-20:00)", "template:3_1")],
+        vec![InlineKeyboardButton::callback("Задать свой шаблон", "template:custom")],
+        vec![InlineKeyboardButton::callback("Удалить шаблон", "template:delete")],
+    ]);
+
+    bot.send_message(chat_id, message)
+        .reply_markup(ReplyMarkup::InlineKeyboard(keyboard))
+        .await?;
+
+    Ok(())
+}
+
 async fn show_photographer_schedule(bot: Bot, msg: &Message, pool: &PgPool, photographer_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let today = time::OffsetDateTime::now_utc();
-    let calendar = generate_calendar(today.month() as u32, today.year(), &pool, photographer_id, UserType::Photographer).await;
+    let today = clock::today();
+    let calendar = generate_calendar(today.month() as u32, today.year(), &pool, photographer_id, None, UserType::Photographer).await;
     
     if let Some(reply_to) = msg.reply_to_message() {
         bot.edit_message_text(msg.chat.id, reply_to.id, "Выберите дату для просмотра или редактирования расписания:")
@@ -2670,7 +3483,7 @@ async fn show_time_slots(bot: Bot, chat_id: ChatId, slots: Vec<String>, message_
 
 async fn notify_photographer(bot: &Bot, photographer_id: i32, pool: &PgPool, booking_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
     let photographer = sqlx::query!(
-        "SELECT telegram_id FROM photographers WHERE id = $1",
+        r#"SELECT telegram_id as "telegram_id: TelegramId" FROM photographers WHERE id = $1"#,
         photographer_id
     )
     .fetch_one(pool)
@@ -2681,9 +3494,9 @@ async fn notify_photographer(bot: &Bot, photographer_id: i32, pool: &PgPool, boo
         r#"
         SELECT 
             b.id,
-            b.booking_start,
-            b.booking_end,
-            b.status,
+            b.booking_start::timestamp as "booking_start!",
+            b.booking_end::timestamp as "booking_end!",
+            b.status as "status: BookingStatus",
             c.name as client_name,
             s.name as service_name,
             b.client_id,
@@ -2717,7 +3530,7 @@ async fn notify_photographer(bot: &Bot, photographer_id: i32, pool: &PgPool, boo
     );
 
     if let Some(telegram_id) = photographer.telegram_id {
-        bot.send_message(ChatId(telegram_id as i64), message)
+        bot.send_message(ChatId::from(telegram_id), message)
         .parse_mode(teloxide::types::ParseMode::Markdown)
         .await?;
     }
@@ -2730,12 +3543,11 @@ async fn add_working_day(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_
     println!("chat_id: {}", chat_id.0);
     
     // Проверяем, есть ли уже рабочие часы на эту дату
-    if let Some((start_hour, end_hour)) = get_working_hours(pool, photographer_id, date).await {
+    if let Some((start_time, end_time)) = get_working_hours(pool, photographer_id, date).await {
         message = format!(
-            "Текущие рабочие часы на {}: {}:00-{}:00\n\nВыберите новые рабочие часы:",
+            "Текущие рабочие часы на {}: {}\n\nВыберите новые рабочие часы:",
             date,
-            start_hour,
-            end_hour
+            Interval::from_times(start_time, end_time).label()
         );
     } else {
         message = format!("Выберите рабочие часы на {}:", date);
@@ -2746,6 +3558,7 @@ async fn add_working_day(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_
         vec![InlineKeyboardButton::callback("9:00-19:00", format!("working_hours:9:19"))],
         vec![InlineKeyboardButton::callback("10:00-18:00", format!("working_hours:10:18"))],
         vec![InlineKeyboardButton::callback("Настроить свои часы", "custom_hours")],
+        vec![InlineKeyboardButton::callback("🚫 Выходной", "working_hours:0:0".to_string())],
     ]);
 
     bot.send_message(chat_id, message)
@@ -2755,16 +3568,16 @@ async fn add_working_day(bot: Bot, chat_id: ChatId, pool: &PgPool, photographer_
     Ok(())
 }
 
-async fn save_working_hours(pool: &PgPool, photographer_id: i32, date: Date, start_hour: i32, end_hour: i32) -> Result<(), sqlx::Error> {
+async fn save_working_hours(pool: &PgPool, photographer_id: i32, date: Date, start_time: Time, end_time: Time) -> Result<(), sqlx::Error> {
     sqlx::query!(
-        "INSERT INTO working_hours (photographer_id, date, start_hour, end_hour)
+        "INSERT INTO working_hours (photographer_id, date, start_time, end_time)
          VALUES ($1, $2, $3, $4)
          ON CONFLICT (photographer_id, date) DO UPDATE
-         SET start_hour = $3, end_hour = $4",
+         SET start_time = $3, end_time = $4",
         photographer_id,
         date,
-        start_hour,
-        end_hour
+        start_time,
+        end_time
     )
     .execute(pool)
     .await?;
@@ -2772,10 +3585,12 @@ async fn save_working_hours(pool: &PgPool, photographer_id: i32, date: Date, sta
     Ok(())
 }
 
-async fn get_working_hours(pool: &PgPool, photographer_id: i32, date: Date) -> Option<(i32, i32)> {
+// Часы на дату с учётом шаблона графика и ручных изменений на конкретный день
+async fn get_working_hours(pool: &PgPool, photographer_id: i32, date: Date) -> Option<(Time, Time)> {
     let hours = sqlx::query!(
-        "SELECT start_hour, end_hour FROM working_hours
-         WHERE photographer_id = $1 AND date = $2",
+        r#"SELECT start_time as "start_time!", end_time as "end_time!"
+         FROM resolved_working_hours($2, $2)
+         WHERE photographer_id = $1"#,
         photographer_id,
         date
     )
@@ -2783,13 +3598,16 @@ async fn get_working_hours(pool: &PgPool, photographer_id: i32, date: Date) -> O
     .await
     .unwrap();
 
-    hours.map(|h| (h.start_hour, h.end_hour))
+    hours.map(|h| (h.start_time, h.end_time))
 }
 
+
 async fn show_client_bookings(bot: Bot, chat_id: ChatId, pool: PgPool, client_id: i32, page: usize, session: &mut UserSession, msg: Message) -> Result<(), Box<dyn Error + Send + Sync>> {
     let bookings = sqlx::query!(
         r#"
-        SELECT b.*, p.name as photographer_name, s.name as service_name
+        SELECT b.id, b.booking_start::timestamp as "booking_start!", b.booking_end::timestamp as "booking_end!", b.status as "status: BookingStatus",
+                               b.price, p.name as photographer_name, s.name as service_name,
+                               EXISTS(SELECT 1 FROM materials m WHERE m.booking_id = b.id) as "has_materials!"
         FROM bookings b
         JOIN photographers p ON b.photographer_id = p.id
         JOIN services s ON b.service_id = s.id
@@ -2825,25 +3643,20 @@ async fn show_client_bookings(bot: Bot, chat_id: ChatId, pool: PgPool, client_id
         let start_time = booking.booking_start.format(&time_format).unwrap();
         let end_time = booking.booking_end.format(&time_format).unwrap();
         
-        let status = match booking.status.as_str() {
-            "new" => "🆕 Новый",
-            "confirmed" => "✅ Подтвержден",
-            "completed" => "✅ Выполнен",
-            "cancelled" => "❌ Отменен",
-            _ => booking.status.as_str()
-        };
+        let status = booking.status.to_string();
         
         message.push_str(&format!(
-            "*Номер записи: {}*\n*Дата:* {}\n*Время:* {} - {}\n*Фотограф: *{}\n*Услуга:* {}\n*Статус:* {}\n\n",
+            "*Номер записи: {}*\n*Дата:* {}\n*Время:* {} - {}\n*Фотограф: *{}\n*Услуга:* {}\n*Стоимость:* {} руб.\n*Статус:* {}\n\n",
             booking.id,
             date,
             start_time,
             end_time,
             booking.photographer_name,
             booking.service_name,
+            booking.price,
             status
         ));
-        if booking.status == "confirmed" || booking.status == "new" {
+        if booking.status == BookingStatus::Confirmed || booking.status == BookingStatus::New {
             let mut booking_buttons = vec![
                 InlineKeyboardButton::callback(
                     format!("🔢 #{}", booking.id),
@@ -2852,11 +3665,20 @@ async fn show_client_bookings(bot: Bot, chat_id: ChatId, pool: PgPool, client_id
                 ];
 
                 // Добавляем кнопки в зависимости от статуса записи
+                    booking_buttons.push(InlineKeyboardButton::callback(
+                        "🔁 Перенести".to_string(),
+                        format!("reschedule:{}", booking.id)
+                    ));
                     booking_buttons.push(InlineKeyboardButton::callback(
                         "❌ Отменить".to_string(),
                         format!("client_reject_booking:{}", booking.id)
                     ));
                 keyboard.push(booking_buttons);
+        } else if booking.has_materials {
+            keyboard.push(vec![InlineKeyboardButton::callback(
+                format!("🖼 Фото по записи №{}", booking.id),
+                format!("materials:{}", booking.id)
+            )]);
         }
     }
 
@@ -2884,35 +3706,6 @@ async fn show_client_bookings(bot: Bot, chat_id: ChatId, pool: PgPool, client_id
     Ok(())
 }
 
-async fn find_available_photographer(pool: &PgPool, service_id: i32, date_time: PrimitiveDateTime) -> Result<Option<Photographer>, sqlx::Error> {
-    // Получаем всех фотографов, которые предоставляют данную услугу
-    let photographers = sqlx::query!(
-        "SELECT p.id FROM photographers p
-         JOIN photographer_services ps ON p.id = ps.photographer_id
-         WHERE ps.service_id = $1",
-        service_id
-    )
-    .fetch_all(pool)
-    .await?;
-
-    // Проверяем каждого фотографа на наличие свободных слотов
-    for photographer in photographers {
-        if let Ok(slots) = get_free_slots(pool, photographer.id, service_id, date_time).await {
-            if !slots.is_empty() {
-                // Если нашли свободного фотографа, возвращаем его данные
-                return Ok(Some(sqlx::query_as::<_, Photographer>(
-                    "SELECT * FROM photographers WHERE id = $1"
-                )
-                .bind(photographer.id)
-                .fetch_one(pool)
-                .await?));
-            }
-        }
-    }
-
-    Ok(None)
-}
-
 // Вспомогательные функции для работы с календарем
 fn month_name(month: u32) -> &'static str {
     match month {
@@ -2952,3 +3745,43 @@ fn days_in_month(month: u32, year: i32) -> u32 {
 
     last_day.day()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Больше i32::MAX; при приведении к i32 превращается в 705032704
+    const LARGE_ID: TelegramId = TelegramId(5_000_000_000);
+
+    #[sqlx::test]
+    async fn client_with_large_id_registers_and_is_found(pool: PgPool) {
+        let client_id = register_client(&pool, LARGE_ID, "Тест", "test").await.unwrap();
+
+        let client = check_client(&pool, LARGE_ID).await.expect("client is found by the full id");
+        assert_eq!(client.id, client_id);
+        assert_eq!(client.telegram_id, LARGE_ID);
+        assert!(check_client(&pool, TelegramId(705_032_704)).await.is_none());
+    }
+
+    #[sqlx::test]
+    async fn repeated_registration_keeps_the_same_client(pool: PgPool) {
+        let first = register_client(&pool, LARGE_ID, "Тест", "test").await.unwrap();
+        let second = register_client(&pool, LARGE_ID, "Новое имя", "test").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(check_client(&pool, LARGE_ID).await.unwrap().name, "Новое имя");
+    }
+
+    #[sqlx::test]
+    async fn photographer_with_large_id_is_found(pool: PgPool) {
+        let photographer_id: i32 = sqlx::query_scalar("INSERT INTO photographers (name, telegram_id) VALUES ($1, $2) RETURNING id")
+            .bind("Тест")
+            .bind(LARGE_ID)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let photographer = check_photographer(&pool, LARGE_ID).await.expect("photographer is found by the full id");
+        assert_eq!(photographer.id, photographer_id);
+        assert!(check_client(&pool, LARGE_ID).await.is_none());
+    }
+}
@@ -0,0 +1,169 @@
+use std::{error::Error, time::Duration};
+
+use log::error;
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, ReplyMarkup}};
+use time::macros::format_description;
+
+use crate::config;
+use crate::handlers::{transition_booking_status, StatusError};
+use crate::models::{BookingStatus, TelegramId};
+use crate::waitlist;
+
+// Как часто фоновые задачи проверяют базу
+const TICK: Duration = Duration::from_secs(60);
+
+/// Фоновые задачи бота. Запускается из `main` и работает до остановки процесса.
+pub async fn run(bot: Bot, pool: PgPool) {
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+        if let Err(e) = send_reminders(&bot, &pool).await {
+            error!("Error sending reminders: {}", e);
+        }
+        if let Err(e) = expire_unconfirmed_bookings(&bot, &pool).await {
+            error!("Error expiring unconfirmed bookings: {}", e);
+        }
+        // Снимает просроченные удержания и подхватывает слоты, освобождённые автоотменой
+        if let Err(e) = waitlist::process(&bot, &pool).await {
+            error!("Error processing waitlist: {}", e);
+        }
+    }
+}
+
+// Напоминания о подтверждённых съёмках клиенту и фотографу
+async fn send_reminders(bot: &Bot, pool: &PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut offsets = config::get().reminder_offsets_hours.clone();
+    // Ближние сроки обрабатываем первыми: если бот был выключен и наступили
+    // сразу оба окна, клиент получит одно напоминание, а не два подряд
+    offsets.sort();
+    offsets.dedup();
+
+    for offset in offsets {
+        let due = sqlx::query!(
+            r#"SELECT b.id, b.booking_start::timestamp as "booking_start!", s.name as service_name,
+                      c.telegram_id as "client_telegram_id: TelegramId",
+                      p.name as photographer_name, p.telegram_id as "photographer_telegram_id: TelegramId"
+               FROM bookings b
+               JOIN services s ON b.service_id = s.id
+               JOIN clients c ON b.client_id = c.id
+               JOIN photographers p ON b.photographer_id = p.id
+               WHERE b.status = 'confirmed'
+               AND b.booking_start > CURRENT_TIMESTAMP
+               AND b.booking_start <= CURRENT_TIMESTAMP + make_interval(hours => $1)
+               AND NOT EXISTS (
+                   SELECT 1 FROM booking_reminders r
+                   WHERE r.booking_id = b.id AND r.offset_hours <= $1
+               )"#,
+            offset
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for booking in due {
+            // Сначала отмечаем напоминание, потом отправляем: после перезапуска оно не уйдёт повторно
+            let claimed = sqlx::query!(
+                "INSERT INTO booking_reminders (booking_id, offset_hours) VALUES ($1, $2)
+                 ON CONFLICT DO NOTHING",
+                booking.id,
+                offset
+            )
+            .execute(pool)
+            .await?
+            .rows_affected() > 0;
+            if !claimed {
+                continue;
+            }
+
+            let format = format_description!("[day].[month].[year] [hour]:[minute]");
+            let start = booking.booking_start.format(&format).unwrap();
+
+            let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("✅ Приду", format!("reminder_confirm:{}", booking.id)),
+                InlineKeyboardButton::callback("❌ Отменить запись", format!("client_reject_booking:{}", booking.id)),
+            ]]);
+            let text = format!(
+                "⏰ Напоминаем о съёмке!\n\n\
+                📸 Услуга: {}\n\
+                👤 Фотограф: {}\n\
+                📅 Начало: {}\n\n\
+                Подтвердите, пожалуйста, что придёте.",
+                booking.service_name, booking.photographer_name, start
+            );
+            if let Err(e) = bot.send_message(ChatId::from(booking.client_telegram_id), text)
+                .reply_markup(ReplyMarkup::InlineKeyboard(keyboard))
+                .await
+            {
+                error!("Failed to send reminder for booking {} to client: {}", booking.id, e);
+            }
+
+            if let Some(telegram_id) = booking.photographer_telegram_id {
+                let text = format!("⏰ Напоминание: запись №{} ({}) начинается {}", booking.id, booking.service_name, start);
+                if let Err(e) = bot.send_message(ChatId::from(telegram_id), text).await {
+                    error!("Failed to send reminder for booking {} to photographer: {}", booking.id, e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Отменяет новые записи, которые фотограф не подтвердил вовремя, и освобождает их слоты
+async fn expire_unconfirmed_bookings(bot: &Bot, pool: &PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let Some(hours) = config::get().booking_confirmation_hours else {
+        return Ok(());
+    };
+
+    // Запись, время которой уже наступило, подтверждать поздно независимо от срока
+    let expired = sqlx::query!(
+        r#"SELECT b.id, b.booking_start::timestamp as "booking_start!", s.name as service_name,
+                  c.telegram_id as "client_telegram_id: TelegramId", p.telegram_id as "photographer_telegram_id: TelegramId",
+                  EXISTS (SELECT 1 FROM payments pm WHERE pm.booking_id = b.id AND pm.status = 'paid') as "paid!"
+           FROM bookings b
+           JOIN services s ON b.service_id = s.id
+           JOIN clients c ON b.client_id = c.id
+           JOIN photographers p ON b.photographer_id = p.id
+           WHERE b.status = 'new'
+           AND (b.confirmation_requested_at <= CURRENT_TIMESTAMP - make_interval(hours => $1)
+                OR b.booking_start <= CURRENT_TIMESTAMP)"#,
+        hours
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for booking in expired {
+        match transition_booking_status(pool, booking.id, BookingStatus::Cancelled).await {
+            Ok(_) => {}
+            // Фотограф успел подтвердить или клиент отменил сам
+            Err(StatusError::IllegalTransition { .. }) | Err(StatusError::NotFound) => continue,
+            Err(e) => return Err(e.into()),
+        }
+
+        let format = format_description!("[day].[month].[year] [hour]:[minute]");
+        let start = booking.booking_start.format(&format).unwrap();
+
+        let mut text = format!(
+            "😔 Фотограф не успел подтвердить запись №{} ({}, {}), поэтому она отменена. Выберите, пожалуйста, другое время.",
+            booking.id, booking.service_name, start
+        );
+        if booking.paid {
+            text.push_str("\n\nОплата по записи будет возвращена, студия свяжется с вами.");
+        }
+        if let Err(e) = bot.send_message(ChatId::from(booking.client_telegram_id), text).await {
+            error!("Failed to notify client about expired booking {}: {}", booking.id, e);
+        }
+
+        if let Some(telegram_id) = booking.photographer_telegram_id {
+            let text = format!(
+                "⌛ Запись №{} ({}, {}) отменена автоматически: она не была подтверждена за {} ч.",
+                booking.id, booking.service_name, start, hours
+            );
+            if let Err(e) = bot.send_message(ChatId::from(telegram_id), text).await {
+                error!("Failed to notify photographer about expired booking {}: {}", booking.id, e);
+            }
+        }
+    }
+
+    Ok(())
+}
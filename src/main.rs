@@ -1,15 +1,33 @@
-use std::{collections::HashMap, sync::Arc};
+use std::sync::Arc;
 
 use db::get_db_pool;
-use handlers::{handle_callback_query, handle_message, UserSession};
-use tokio::sync::Mutex;
+use handlers::{handle_callback_query, handle_message};
+use log::error;
+use session::{ChatLocks, InMemorySessionStore, PgSessionStore, SessionStore};
+use sqlx::PgPool;
 mod models;
 mod db;
 mod handlers;
+mod session;
+mod config;
+mod slots;
+mod schedule;
+mod payments;
+mod jobs;
+mod admin;
+mod catalog;
+mod pricing;
+mod promo;
+mod materials;
+mod reviews;
+mod waitlist;
+mod clock;
+mod availability;
+mod assignment;
 use teloxide::{
     dispatching::UpdateFilterExt,
     prelude::*,
-    types::{CallbackQuery, MaybeInaccessibleMessage},
+    types::{CallbackQuery, MaybeInaccessibleMessage, PreCheckoutQuery},
 };
 
 extern crate pretty_env_logger;
@@ -21,40 +39,93 @@ async fn main() {
     let pool = get_db_pool().await;
     let bot = Bot::from_env();
 
-    let bot = bot.clone();
-    let pool = pool.clone();
-    let user_sessions = Arc::new(Mutex::new(HashMap::<i64, UserSession>::new()));
+    tokio::spawn(jobs::run(bot.clone(), pool.clone()));
 
-    let handler = dptree::entry() 
+    // SESSION_STORE=memory — сессии только в памяти (без таблицы user_sessions)
+    match std::env::var("SESSION_STORE").as_deref() {
+        Ok("memory") => run(bot, pool, InMemorySessionStore::new()).await,
+        _ => run(bot, pool.clone(), PgSessionStore::new(pool)).await,
+    }
+}
+
+async fn run<S: SessionStore>(bot: Bot, pool: PgPool, store: S) {
+    let store = Arc::new(store);
+    let chat_locks = Arc::new(ChatLocks::new());
+
+    let handler = dptree::entry()
     .branch(
         Update::filter_message().endpoint({
         let pool = pool.clone();
-        let user_sessions = user_sessions.clone();
+        let store = store.clone();
+        let chat_locks = chat_locks.clone();
 
-        move |bot, msg| {
+        move |bot: Bot, msg: Message| {
             let pool = pool.clone();
-            let user_sessions = user_sessions.clone();
+            let store = store.clone();
+            let chat_locks = chat_locks.clone();
 
             async move {
-                let mut sessions = user_sessions.lock().await;
-                handle_message(bot, msg, pool, &mut sessions).await;
+                let chat_id = msg.chat.id.0;
+                let _guard = chat_locks.lock(chat_id).await;
+                let mut session = match store.load(chat_id).await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        error!("Failed to load session for chat {}: {}", chat_id, e);
+                        return respond(());
+                    }
+                };
+                if let Err(e) = handle_message(msg, bot, pool, &mut session).await {
+                    error!("Error handling message: {}", e);
+                }
+                if let Err(e) = store.save(chat_id, &session).await {
+                    error!("Failed to save session for chat {}: {}", chat_id, e);
+                }
                 respond(())
             }
         }
     }))
     .branch(Update::filter_callback_query().endpoint({
         let pool = pool.clone();
-        let user_sessions = user_sessions.clone();
-    
+        let store = store.clone();
+        let chat_locks = chat_locks.clone();
+
         move |q: CallbackQuery, bot: Bot| {
             let pool = pool.clone();
-            let user_sessions = user_sessions.clone();
-    
+            let store = store.clone();
+            let chat_locks = chat_locks.clone();
+
             async move {
-                let mut sessions = user_sessions.lock().await;
                 let query = q.clone();
                 if let Some(message) = MaybeInaccessibleMessage::regular_message(&query.message.unwrap()) {
-                    handle_callback_query(q, bot, message.clone(), pool, &mut sessions).await;
+                    let chat_id = message.chat.id.0;
+                    let _guard = chat_locks.lock(chat_id).await;
+                    let mut session = match store.load(chat_id).await {
+                        Ok(session) => session,
+                        Err(e) => {
+                            error!("Failed to load session for chat {}: {}", chat_id, e);
+                            return respond(());
+                        }
+                    };
+                    if let Err(e) = handle_callback_query(q, bot, message.clone(), pool, &mut session).await {
+                        error!("Error handling callback query: {}", e);
+                    }
+                    if let Err(e) = store.save(chat_id, &session).await {
+                        error!("Failed to save session for chat {}: {}", chat_id, e);
+                    }
+                }
+                respond(())
+            }
+        }
+    }))
+    .branch(Update::filter_pre_checkout_query().endpoint({
+        let pool = pool.clone();
+
+        move |q: PreCheckoutQuery, bot: Bot| {
+            let pool = pool.clone();
+
+            async move {
+                if let Err(e) = payments::handle_pre_checkout_query(bot, q, pool).await {
+                    error!("Error handling pre-checkout query: {}", e);
                 }
                 respond(())
             }
@@ -62,9 +133,8 @@ async fn main() {
     }));
 
     Dispatcher::builder(bot, handler)
-        .dependencies(dptree::deps![user_sessions])
         .enable_ctrlc_handler()
         .build()
         .dispatch()
         .await;
-}
\ No newline at end of file
+}
@@ -0,0 +1,223 @@
+use std::error::Error;
+
+use log::error;
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, InputFile, InputMedia, InputMediaPhoto, Message}};
+use url::Url;
+
+use crate::models::{BookingStatus, Material, TelegramId};
+use crate::session::{UserSession, UserStep};
+
+// Готовые фото по завершённой записи. Фотограф пересылает файлы в бот
+// (сохраняется file_id Telegram) или присылает ссылки на альбомы
+
+const KIND_PHOTO: &str = "photo";
+const KIND_VIDEO: &str = "video";
+const KIND_DOCUMENT: &str = "document";
+const KIND_LINK: &str = "link";
+
+// В одном альбоме Telegram не больше 10 фото
+const MEDIA_GROUP_LIMIT: usize = 10;
+
+pub async fn get_materials(pool: &PgPool, booking_id: i32) -> Result<Vec<Material>, sqlx::Error> {
+    sqlx::query_as::<_, Material>(
+        "SELECT id, booking_id, kind, file_url FROM materials WHERE booking_id = $1 ORDER BY id"
+    )
+    .bind(booking_id)
+    .fetch_all(pool)
+    .await
+}
+
+// Файлы и ссылки из одного сообщения фотографа
+fn extract_materials(msg: &Message) -> Vec<(&'static str, String)> {
+    let mut materials = Vec::new();
+
+    if let Some(photo) = msg.photo().and_then(|sizes| sizes.last()) {
+        materials.push((KIND_PHOTO, photo.file.id.clone()));
+    }
+    if let Some(video) = msg.video() {
+        materials.push((KIND_VIDEO, video.file.id.clone()));
+    }
+    if let Some(document) = msg.document() {
+        materials.push((KIND_DOCUMENT, document.file.id.clone()));
+    }
+
+    let text = msg.text().or(msg.caption()).unwrap_or("");
+    for word in text.split_whitespace() {
+        if let Ok(url) = Url::parse(word) {
+            if url.scheme() == "http" || url.scheme() == "https" {
+                materials.push((KIND_LINK, url.to_string()));
+            }
+        }
+    }
+
+    materials
+}
+
+async fn photographer_booking(pool: &PgPool, session: &UserSession, booking_id: i32) -> Result<bool, sqlx::Error> {
+    let status = sqlx::query_scalar!(
+        r#"SELECT status as "status: BookingStatus" FROM bookings WHERE id = $1 AND photographer_id = $2"#,
+        booking_id,
+        session.photographer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(status == Some(BookingStatus::Completed))
+}
+
+/// Кнопки материалов: `materials_upload:`, `materials_done:` у фотографа и `materials:` у клиента.
+pub async fn handle_callback(bot: &Bot, chat_id: ChatId, data: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let parts: Vec<&str> = data.split(':').collect();
+    match parts.as_slice() {
+        ["materials_upload", id] => {
+            let booking_id: i32 = id.parse()?;
+            if !photographer_booking(pool, session, booking_id).await? {
+                bot.send_message(chat_id, "Фото можно прикрепить только к своей завершённой записи").await?;
+                return Ok(());
+            }
+            session.step = UserStep::UploadMaterials;
+            session.edit_target_id = Some(booking_id);
+            let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("✅ Готово", format!("materials_done:{}", booking_id)),
+            ]]);
+            bot.send_message(
+                chat_id,
+                format!(
+                    "Отправьте фото, видео или файлы для записи №{} — можно альбомом. \
+                    Ссылки на облако тоже подойдут. Когда закончите, нажмите «Готово», и клиент получит уведомление.",
+                    booking_id
+                ),
+            )
+            .reply_markup(keyboard)
+            .await?;
+        }
+        ["materials_done", id] => {
+            let booking_id: i32 = id.parse()?;
+            session.step = UserStep::PhotographerMainMenu;
+            session.edit_target_id = None;
+            if !photographer_booking(pool, session, booking_id).await? {
+                return Ok(());
+            }
+            let count = get_materials(pool, booking_id).await?.len();
+            if count == 0 {
+                bot.send_message(chat_id, "Вы ничего не прикрепили к записи").await?;
+                return Ok(());
+            }
+            bot.send_message(chat_id, format!("Материалы по записи №{} сохранены: {}", booking_id, count)).await?;
+            if let Err(e) = notify_client(bot, pool, booking_id).await {
+                error!("Failed to notify client about materials for booking {}: {}", booking_id, e);
+            }
+        }
+        ["materials", id] => {
+            let booking_id: i32 = id.parse()?;
+            let owner = sqlx::query_scalar!(r#"SELECT client_id AS "client_id!" FROM bookings WHERE id = $1"#, booking_id)
+                .fetch_optional(pool)
+                .await?;
+            if owner != Some(session.client_id) {
+                return Ok(());
+            }
+            send_materials(bot, chat_id, pool, booking_id).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Сообщения фотографа на шаге `UploadMaterials`.
+pub async fn handle_upload(bot: &Bot, msg: &Message, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let Some(booking_id) = session.edit_target_id else {
+        session.step = UserStep::PhotographerMainMenu;
+        return Ok(());
+    };
+
+    let materials = extract_materials(msg);
+    if materials.is_empty() {
+        bot.send_message(chat_id, "Отправьте фото, видео, файл или ссылку. Чтобы закончить, нажмите «Готово».").await?;
+        return Ok(());
+    }
+
+    for (kind, file_url) in &materials {
+        sqlx::query!(
+            "INSERT INTO materials (booking_id, kind, file_url) VALUES ($1, $2, $3)",
+            booking_id,
+            kind,
+            file_url
+        )
+        .execute(pool)
+        .await?;
+    }
+
+    // Альбом приходит отдельными сообщениями, на каждое фото не отвечаем
+    if msg.media_group_id().is_none() {
+        let total = get_materials(pool, booking_id).await?.len();
+        bot.send_message(chat_id, format!("Добавлено. Всего материалов по записи №{}: {}", booking_id, total)).await?;
+    }
+    Ok(())
+}
+
+async fn notify_client(bot: &Bot, pool: &PgPool, booking_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let client = sqlx::query!(
+        r#"SELECT c.telegram_id as "telegram_id: TelegramId" FROM bookings b
+         JOIN clients c ON b.client_id = c.id
+         WHERE b.id = $1"#,
+        booking_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![
+        InlineKeyboardButton::callback("🖼 Посмотреть", format!("materials:{}", booking_id)),
+    ]]);
+    bot.send_message(
+        ChatId::from(client.telegram_id),
+        format!("📸 Фотограф прислал готовые материалы по записи №{}. Они также доступны в истории записей.", booking_id),
+    )
+    .reply_markup(keyboard)
+    .await?;
+    Ok(())
+}
+
+async fn send_materials(bot: &Bot, chat_id: ChatId, pool: &PgPool, booking_id: i32) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let materials = get_materials(pool, booking_id).await?;
+    if materials.is_empty() {
+        bot.send_message(chat_id, "Фотограф ещё не прислал материалы по этой записи").await?;
+        return Ok(());
+    }
+
+    let photos: Vec<&Material> = materials.iter().filter(|m| m.kind == KIND_PHOTO).collect();
+    for chunk in photos.chunks(MEDIA_GROUP_LIMIT) {
+        if let [photo] = chunk {
+            bot.send_photo(chat_id, InputFile::file_id(photo.file_url.clone())).await?;
+        } else {
+            let media = chunk
+                .iter()
+                .map(|photo| InputMedia::Photo(InputMediaPhoto::new(InputFile::file_id(photo.file_url.clone()))))
+                .collect::<Vec<_>>();
+            bot.send_media_group(chat_id, media).await?;
+        }
+    }
+
+    for material in &materials {
+        match material.kind.as_str() {
+            KIND_VIDEO => {
+                bot.send_video(chat_id, InputFile::file_id(material.file_url.clone())).await?;
+            }
+            KIND_DOCUMENT => {
+                bot.send_document(chat_id, InputFile::file_id(material.file_url.clone())).await?;
+            }
+            _ => {}
+        }
+    }
+
+    let links: Vec<&str> = materials
+        .iter()
+        .filter(|m| m.kind == KIND_LINK)
+        .map(|m| m.file_url.as_str())
+        .collect();
+    if !links.is_empty() {
+        bot.send_message(chat_id, format!("Ссылки на материалы по записи №{}:\n{}", booking_id, links.join("\n"))).await?;
+    }
+
+    Ok(())
+}
@@ -1,5 +1,32 @@
+use std::fmt;
+
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use teloxide::types::ChatId;
+use time::PrimitiveDateTime;
+
+/// Telegram ID пользователя. Новые аккаунты давно вышли за пределы i32,
+/// поэтому ID везде хранится и передаётся как BIGINT без приведений.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct TelegramId(pub i64);
+
+impl From<ChatId> for TelegramId {
+    fn from(chat_id: ChatId) -> Self {
+        TelegramId(chat_id.0)
+    }
+}
+
+impl From<TelegramId> for ChatId {
+    fn from(telegram_id: TelegramId) -> Self {
+        ChatId(telegram_id.0)
+    }
+}
+
+impl fmt::Display for TelegramId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 #[derive(Debug, FromRow)]
 pub struct Photographer {
@@ -7,12 +34,13 @@ pub struct Photographer {
     pub name: String,
     pub description: Option<String>,
     pub portfolio_url: Option<String>,
+    pub is_active: bool,
 }
 
 #[derive(Debug, FromRow)]
 pub struct Client {
     pub id: i32,
-    pub telegram_id: i64,
+    pub telegram_id: TelegramId,
     pub name: String,
 }
 
@@ -22,15 +50,60 @@ pub struct Booking {
     pub client_id: i32,
     pub photographer_id: i32,
     pub service: String,
-    pub booking_start: DateTime<Utc>,
-    pub booking_end: DateTime<Utc>,
-    pub status: String,
+    // Местное время студии
+    pub booking_start: PrimitiveDateTime,
+    pub booking_end: PrimitiveDateTime,
+    pub status: BookingStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, sqlx::Type)]
+#[sqlx(type_name = "booking_status", rename_all = "lowercase")]
+pub enum BookingStatus {
+    New,
+    Confirmed,
+    Completed,
+    Cancelled
+}
+
+impl std::fmt::Display for BookingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BookingStatus::New => "🆕 Новый",
+            BookingStatus::Confirmed => "✅ Подтвержден",
+            BookingStatus::Completed => "✅ Выполнен",
+            BookingStatus::Cancelled => "❌ Отменен"
+        })
+    }
+}
+
+impl BookingStatus {
+    // Допустимые переходы: новая запись подтверждается или отменяется,
+    // подтверждённая — завершается или отменяется. Завершённые и отменённые
+    // не меняются
+    pub fn can_transition_to(&self, next: BookingStatus) -> bool {
+        matches!(
+            (self, next),
+            (BookingStatus::New, BookingStatus::Confirmed)
+                | (BookingStatus::New, BookingStatus::Cancelled)
+                | (BookingStatus::Confirmed, BookingStatus::Completed)
+                | (BookingStatus::Confirmed, BookingStatus::Cancelled)
+        )
+    }
+
+    // Перенос: новая или подтверждённая запись снова ждёт подтверждения
+    // фотографа. Отдельно от общей таблицы, чтобы вернуть подтверждённую
+    // запись в новые можно было только переносом
+    pub fn can_reschedule(&self) -> bool {
+        matches!(self, BookingStatus::New | BookingStatus::Confirmed)
+    }
 }
 
 #[derive(Debug, FromRow)]
 pub struct Material {
     pub id: i32,
     pub booking_id: i32,
+    // photo, video, document — file_url содержит file_id Telegram; link — ссылку
+    pub kind: String,
     pub file_url: String,
 }
 
@@ -41,6 +114,10 @@ pub struct Service {
     pub cost: i32,
     pub duration: i32,
     pub comment: Option<String>,
+    // Минуты на подготовку студии до съёмки и уборку после
+    pub buffer_before: i32,
+    pub buffer_after: i32,
+    pub is_active: bool,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -48,4 +125,29 @@ pub struct PhotographerService {
     pub id: i32,
     pub photographer_id: i32,
     pub service_id: i32,
+    // Личные цена и длительность фотографа, если отличаются от каталога
+    pub price_override: Option<i32>,
+    pub duration_override: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ID реального аккаунта из тестовых данных, больше i32::MAX
+    const LARGE_ID: i64 = 6_577_990_046;
+
+    #[test]
+    fn large_id_survives_chat_id_round_trip() {
+        assert!(LARGE_ID > i32::MAX as i64);
+        let telegram_id = TelegramId::from(ChatId(LARGE_ID));
+        assert_eq!(telegram_id, TelegramId(LARGE_ID));
+        assert_eq!(ChatId::from(telegram_id), ChatId(LARGE_ID));
+    }
+
+    #[test]
+    fn large_id_is_displayed_in_full() {
+        assert_eq!(TelegramId(LARGE_ID).to_string(), "6577990046");
+        assert_eq!(TelegramId(i64::MAX).to_string(), i64::MAX.to_string());
+    }
 }
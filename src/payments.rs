@@ -0,0 +1,164 @@
+use std::error::Error;
+
+use log::error;
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{LabeledPrice, PreCheckoutQuery, SuccessfulPayment}};
+
+use crate::config;
+use crate::models::{BookingStatus, TelegramId};
+
+// Счёт выставляется с payload "payment:{id}", по нему находим запись в таблице payments
+fn parse_payload(payload: &str) -> Option<i32> {
+    payload.strip_prefix("payment:")?.parse().ok()
+}
+
+/// Выставляет клиенту счёт за запись.
+///
+/// Возвращает `false`, если онлайн-оплата не настроена (нет `PAYMENT_PROVIDER_TOKEN`)
+/// или платить нечего — промокод покрыл всю цену.
+pub async fn send_booking_invoice(bot: &Bot, pool: &PgPool, chat_id: ChatId, booking_id: i32) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let config = config::get();
+    let Some(provider_token) = config.payment_provider_token.clone() else {
+        return Ok(false);
+    };
+
+    let service = sqlx::query!(
+        "SELECT s.name, b.price FROM bookings b
+         JOIN services s ON b.service_id = s.id
+         WHERE b.id = $1",
+        booking_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    // Цена записи хранится в рублях, Telegram ждёт сумму в копейках
+    let amount = service.price * config.prepayment_percent;
+    if amount <= 0 {
+        return Ok(false);
+    }
+    let payment_id = sqlx::query_scalar!(
+        "INSERT INTO payments (booking_id, amount, currency, percent)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+        booking_id,
+        amount,
+        config.payment_currency,
+        config.prepayment_percent
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let (title, label) = if config.prepayment_percent == 100 {
+        (format!("Оплата записи №{}", booking_id), service.name.clone())
+    } else {
+        (
+            format!("Предоплата записи №{}", booking_id),
+            format!("{} — предоплата {}%", service.name, config.prepayment_percent),
+        )
+    };
+
+    bot.send_invoice(
+        chat_id,
+        title,
+        format!("{}. Остаток оплачивается в студии.", service.name),
+        format!("payment:{}", payment_id),
+        config.payment_currency.clone(),
+        vec![LabeledPrice { label, amount: amount as u32 }],
+    )
+    .provider_token(provider_token)
+    .await?;
+
+    Ok(true)
+}
+
+pub async fn handle_pre_checkout_query(bot: Bot, q: PreCheckoutQuery, pool: PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let payment = match parse_payload(&q.invoice_payload) {
+        Some(payment_id) => sqlx::query!(
+            r#"SELECT p.amount, p.currency, p.status, b.status as "booking_status: BookingStatus"
+             FROM payments p
+             JOIN bookings b ON p.booking_id = b.id
+             WHERE p.id = $1"#,
+            payment_id
+        )
+        .fetch_optional(&pool)
+        .await?,
+        None => None,
+    };
+
+    // Проверяем, что счёт ещё актуален: не оплачен, сумма не изменилась, запись не отменена
+    let error_message = match payment {
+        None => Some("Счёт не найден"),
+        Some(p) if p.status != "pending" => Some("Этот счёт уже оплачен"),
+        Some(p) if p.booking_status == BookingStatus::Cancelled => Some("Запись отменена"),
+        Some(p) if p.amount as u32 != q.total_amount || p.currency != q.currency => Some("Сумма счёта изменилась"),
+        Some(_) => None,
+    };
+
+    match error_message {
+        None => {
+            bot.answer_pre_checkout_query(q.id, true).await?;
+        }
+        Some(message) => {
+            bot.answer_pre_checkout_query(q.id, false)
+                .error_message(message)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn handle_successful_payment(bot: &Bot, pool: &PgPool, chat_id: ChatId, payment: &SuccessfulPayment) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let Some(payment_id) = parse_payload(&payment.invoice_payload) else {
+        error!("Unknown invoice payload: {}", payment.invoice_payload);
+        return Ok(());
+    };
+
+    // Повторная доставка того же платежа ничего не меняет
+    let paid = sqlx::query!(
+        "UPDATE payments
+         SET status = 'paid',
+             telegram_payment_charge_id = $2,
+             provider_payment_charge_id = $3,
+             paid_at = CURRENT_TIMESTAMP
+         WHERE id = $1 AND status = 'pending'
+         RETURNING booking_id",
+        payment_id,
+        payment.telegram_payment_charge_id.to_string(),
+        payment.provider_payment_charge_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(paid) = paid else {
+        return Ok(());
+    };
+
+    bot.send_message(chat_id, format!("✅ Оплата по записи №{} получена. Ожидайте подтверждения фотографа.", paid.booking_id))
+        .await?;
+
+    let photographer = sqlx::query!(
+        r#"SELECT p.telegram_id as "telegram_id: TelegramId" FROM bookings b
+         JOIN photographers p ON b.photographer_id = p.id
+         WHERE b.id = $1"#,
+        paid.booking_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(telegram_id) = photographer.and_then(|p| p.telegram_id) {
+        if let Err(e) = bot.send_message(
+            ChatId::from(telegram_id),
+            format!(
+                "💳 Клиент оплатил запись №{}: {:.2} {}",
+                paid.booking_id,
+                payment.total_amount as f64 / 100.0,
+                payment.currency
+            ),
+        ).await {
+            error!("Failed to notify photographer about payment: {}", e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+use time::{PrimitiveDateTime, Time};
+
+use crate::schedule::{parse_hours_range, parse_weekdays, weekdays_label};
+
+// Скидка студии: действует, если съёмка начинается в один из дней weekdays
+// в интервале [start_time, end_time)
+#[derive(Debug, Clone, FromRow)]
+pub struct PriceRule {
+    pub id: i32,
+    pub weekdays: Vec<i32>,
+    pub start_time: Time,
+    pub end_time: Time,
+    pub percent_off: i32,
+}
+
+impl PriceRule {
+    fn applies(&self, start: PrimitiveDateTime) -> bool {
+        let weekday = start.date().weekday().number_from_monday() as i32;
+        self.weekdays.contains(&weekday) && start.time() >= self.start_time && start.time() < self.end_time
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}:{:02}-{}:{:02} −{}%",
+            weekdays_label(&self.weekdays),
+            self.start_time.hour(), self.start_time.minute(),
+            self.end_time.hour(), self.end_time.minute(),
+            self.percent_off
+        )
+    }
+}
+
+// Цена услуги для конкретного фотографа и времени
+#[derive(Debug, Clone, Copy)]
+pub struct Quote {
+    // Цена по каталогу или личная цена фотографа
+    pub base: i32,
+    pub price: i32,
+    pub percent_off: i32,
+}
+
+impl Quote {
+    pub fn describe(&self) -> String {
+        if self.percent_off > 0 {
+            format!("{} руб. (скидка {}%, без скидки {} руб.)", self.price, self.percent_off, self.base)
+        } else {
+            format!("{} руб.", self.price)
+        }
+    }
+}
+
+/// Применяет наибольшую из подходящих скидок. Скидки не суммируются.
+pub fn apply_rules(base: i32, rules: &[PriceRule], start: PrimitiveDateTime) -> Quote {
+    let percent_off = rules
+        .iter()
+        .filter(|rule| rule.applies(start))
+        .map(|rule| rule.percent_off)
+        .max()
+        .unwrap_or(0);
+    // Округляем в пользу клиента
+    let price = base * (100 - percent_off) / 100;
+    Quote { base, price, percent_off }
+}
+
+// Принимает соединение, чтобы цену можно было посчитать внутри транзакции записи
+pub async fn quote(conn: &mut PgConnection, photographer_id: i32, service_id: i32, start: PrimitiveDateTime) -> Result<Quote, sqlx::Error> {
+    let base = sqlx::query_scalar!(
+        r#"SELECT COALESCE(ps.price_override, s.cost) as "cost!"
+         FROM services s
+         LEFT JOIN photographer_services ps ON ps.service_id = s.id AND ps.photographer_id = $2
+         WHERE s.id = $1"#,
+        service_id,
+        photographer_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let rules = get_rules(conn).await?;
+    Ok(apply_rules(base, &rules, start))
+}
+
+pub async fn get_rules<'e>(executor: impl PgExecutor<'e>) -> Result<Vec<PriceRule>, sqlx::Error> {
+    sqlx::query_as::<_, PriceRule>(
+        "SELECT id, weekdays, start_time, end_time, percent_off FROM price_rules ORDER BY id"
+    )
+    .fetch_all(executor)
+    .await
+}
+
+/// Разбирает скидку, введённую администратором: `Пн-Пт 10:00-13:00 20%`.
+pub fn parse_rule(text: &str) -> Option<(Vec<i32>, Time, Time, i32)> {
+    let mut parts = text.split_whitespace();
+    let weekdays = parse_weekdays(&parts.next()?.to_lowercase())?;
+    let (start_time, end_time) = parse_hours_range(parts.next()?)?;
+    let percent_off: i32 = parts.next()?.trim_end_matches('%').parse().ok()?;
+    if !(1..=100).contains(&percent_off) || parts.next().is_some() {
+        return None;
+    }
+    Some((weekdays, start_time, end_time, percent_off))
+}
+
+pub async fn add_rule(pool: &PgPool, weekdays: Vec<i32>, start_time: Time, end_time: Time, percent_off: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO price_rules (weekdays, start_time, end_time, percent_off) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(weekdays)
+    .bind(start_time)
+    .bind(end_time)
+    .bind(percent_off)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_rule(pool: &PgPool, rule_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM price_rules WHERE id = $1")
+        .bind(rule_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{datetime, time};
+
+    use super::*;
+
+    fn rule(id: i32, weekdays: Vec<i32>, start_time: Time, end_time: Time, percent_off: i32) -> PriceRule {
+        PriceRule { id, weekdays, start_time, end_time, percent_off }
+    }
+
+    fn rules() -> Vec<PriceRule> {
+        vec![
+            rule(1, vec![1, 2, 3, 4, 5], time!(10:00), time!(13:00), 20),
+            rule(2, vec![1], time!(9:00), time!(12:00), 30),
+        ]
+    }
+
+    #[test]
+    fn largest_matching_discount_wins() {
+        // 2 июня 2025 — понедельник
+        let quote = apply_rules(5000, &rules(), datetime!(2025-06-02 11:00));
+        assert_eq!((quote.percent_off, quote.price), (30, 3500));
+
+        let quote = apply_rules(5000, &rules(), datetime!(2025-06-03 11:00));
+        assert_eq!((quote.percent_off, quote.price), (20, 4000));
+    }
+
+    #[test]
+    fn rule_end_is_exclusive() {
+        let quote = apply_rules(5000, &rules(), datetime!(2025-06-02 12:00));
+        assert_eq!(quote.percent_off, 20);
+        let quote = apply_rules(5000, &rules(), datetime!(2025-06-03 13:00));
+        assert_eq!((quote.percent_off, quote.price), (0, 5000));
+    }
+
+    #[test]
+    fn no_discount_outside_rule_days() {
+        let quote = apply_rules(5000, &rules(), datetime!(2025-06-07 11:00));
+        assert_eq!((quote.base, quote.price, quote.percent_off), (5000, 5000, 0));
+    }
+
+    #[test]
+    fn price_is_rounded_down() {
+        let quote = apply_rules(1999, &rules(), datetime!(2025-06-03 10:00));
+        assert_eq!(quote.price, 1599);
+        let quote = apply_rules(1001, &[rule(1, vec![2], time!(0:00), time!(23:59), 15)], datetime!(2025-06-03 10:00));
+        assert_eq!(quote.price, 850);
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            parse_rule("Пн-Пт 10:00-13:00 20%"),
+            Some((vec![1, 2, 3, 4, 5], time!(10:00), time!(13:00), 20))
+        );
+        assert_eq!(parse_rule("сб,вс 9:00-12:00 15"), Some((vec![6, 7], time!(9:00), time!(12:00), 15)));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert_eq!(parse_rule("Пн-Пт 10:00-13:00 0%"), None);
+        assert_eq!(parse_rule("Пн-Пт 10:00-13:00 101%"), None);
+        assert_eq!(parse_rule("Пн-Пт 13:00-10:00 20%"), None);
+        assert_eq!(parse_rule("Пн-Пт 10:00-13:00"), None);
+        assert_eq!(parse_rule("Пн-Пт 10:00-13:00 20% ещё"), None);
+    }
+}
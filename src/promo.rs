@@ -0,0 +1,253 @@
+use sqlx::{FromRow, PgPool, Postgres, Transaction};
+use time::{macros::format_description, Date, PrimitiveDateTime, Time};
+
+// Промокоды вводятся клиентом при подтверждении записи. Скидка применяется
+// к цене после скидок студии, использованным считается код в неотменённой записи
+
+const PROMO_SELECT: &str = "SELECT id, code, percent_off, amount_off, valid_until, max_uses, service_ids, is_active,
+        (valid_from IS NULL OR valid_from <= CURRENT_TIMESTAMP)
+            AND (valid_until IS NULL OR valid_until > CURRENT_TIMESTAMP) AS is_current,
+        (SELECT COUNT(*) FROM bookings b WHERE b.promo_code_id = promo_codes.id AND b.status <> 'cancelled') AS uses
+    FROM promo_codes";
+
+#[derive(Debug, Clone, FromRow)]
+pub struct PromoCode {
+    pub id: i32,
+    pub code: String,
+    pub percent_off: Option<i32>,
+    pub amount_off: Option<i32>,
+    pub valid_until: Option<PrimitiveDateTime>,
+    pub max_uses: Option<i32>,
+    // Пустой список — код действует на все услуги
+    pub service_ids: Option<Vec<i32>>,
+    pub is_active: bool,
+    // Текущее время попадает в срок действия
+    pub is_current: bool,
+    pub uses: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromoError {
+    NotFound,
+    Expired,
+    WrongService,
+    LimitReached,
+}
+
+impl PromoError {
+    pub fn message(&self) -> &'static str {
+        match self {
+            PromoError::NotFound => "Такого промокода нет",
+            PromoError::Expired => "Срок действия промокода истёк или ещё не начался",
+            PromoError::WrongService => "Промокод не действует на эту услугу",
+            PromoError::LimitReached => "Промокод больше нельзя использовать",
+        }
+    }
+}
+
+impl PromoCode {
+    /// Проверяет, можно ли применить код к записи на услугу.
+    pub fn check(&self, service_id: i32) -> Result<(), PromoError> {
+        if !self.is_active {
+            return Err(PromoError::NotFound);
+        }
+        if !self.is_current {
+            return Err(PromoError::Expired);
+        }
+        if let Some(service_ids) = &self.service_ids {
+            if !service_ids.is_empty() && !service_ids.contains(&service_id) {
+                return Err(PromoError::WrongService);
+            }
+        }
+        if self.max_uses.is_some_and(|max_uses| self.uses >= max_uses as i64) {
+            return Err(PromoError::LimitReached);
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, price: i32) -> i32 {
+        match (self.percent_off, self.amount_off) {
+            (Some(percent_off), _) => price * (100 - percent_off) / 100,
+            (None, Some(amount_off)) => (price - amount_off).max(0),
+            (None, None) => price,
+        }
+    }
+
+    pub fn discount_label(&self) -> String {
+        match (self.percent_off, self.amount_off) {
+            (Some(percent_off), _) => format!("−{}%", percent_off),
+            (None, Some(amount_off)) => format!("−{} руб.", amount_off),
+            (None, None) => String::new(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let format = format_description!("[day].[month].[year]");
+        let mut text = format!("{} {}", self.code, self.discount_label());
+        if let Some(valid_until) = self.valid_until {
+            // valid_until хранится как начало следующего дня
+            let last_day = valid_until.date().previous_day().unwrap_or(valid_until.date());
+            text.push_str(&format!(", до {}", last_day.format(&format).unwrap()));
+        }
+        match self.max_uses {
+            Some(max_uses) => text.push_str(&format!(", {}/{}", self.uses, max_uses)),
+            None => text.push_str(&format!(", использован {}", self.uses)),
+        }
+        text
+    }
+}
+
+// Коды хранятся в верхнем регистре, клиент может ввести в любом
+fn normalize(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+pub async fn find(pool: &PgPool, code: &str) -> Result<Option<PromoCode>, sqlx::Error> {
+    sqlx::query_as::<_, PromoCode>(&format!("{} WHERE code = $1", PROMO_SELECT))
+        .bind(normalize(code))
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get(pool: &PgPool, promo_code_id: i32) -> Result<Option<PromoCode>, sqlx::Error> {
+    sqlx::query_as::<_, PromoCode>(&format!("{} WHERE id = $1", PROMO_SELECT))
+        .bind(promo_code_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Блокирует промокод до конца транзакции записи, чтобы лимит использований
+/// не превысили параллельные записи.
+pub async fn lock(tx: &mut Transaction<'_, Postgres>, promo_code_id: i32) -> Result<Option<PromoCode>, sqlx::Error> {
+    let locked = sqlx::query_scalar::<_, i32>("SELECT id FROM promo_codes WHERE id = $1 FOR UPDATE")
+        .bind(promo_code_id)
+        .fetch_optional(&mut **tx)
+        .await?;
+    if locked.is_none() {
+        return Ok(None);
+    }
+
+    // Использования считаем отдельным запросом: в READ COMMITTED он видит записи,
+    // которые параллельная транзакция сохранила, пока мы ждали блокировку
+    sqlx::query_as::<_, PromoCode>(&format!("{} WHERE id = $1", PROMO_SELECT))
+        .bind(promo_code_id)
+        .fetch_optional(&mut **tx)
+        .await
+}
+
+pub async fn get_all(pool: &PgPool) -> Result<Vec<PromoCode>, sqlx::Error> {
+    sqlx::query_as::<_, PromoCode>(&format!("{} ORDER BY is_active DESC, id DESC", PROMO_SELECT))
+        .fetch_all(pool)
+        .await
+}
+
+pub struct NewPromoCode {
+    pub code: String,
+    pub percent_off: Option<i32>,
+    pub amount_off: Option<i32>,
+    pub valid_from: Option<PrimitiveDateTime>,
+    pub valid_until: Option<PrimitiveDateTime>,
+    pub max_uses: Option<i32>,
+    pub service_ids: Option<Vec<i32>>,
+}
+
+// "-" или пустое поле — ограничения нет
+fn optional(value: Option<&str>) -> Option<&str> {
+    value.filter(|v| !v.is_empty() && *v != "-")
+}
+
+fn parse_date(value: &str) -> Option<Date> {
+    Date::parse(value.trim(), format_description!("[day].[month].[year]")).ok()
+}
+
+/// Разбирает промокод, введённый администратором:
+/// `КОД; 15% или 500; 01.06.2025-31.08.2025; лимит; id услуг через запятую`.
+/// Все поля после скидки необязательны.
+pub fn parse_promo(text: &str) -> Option<NewPromoCode> {
+    let mut parts = text.split(';').map(str::trim);
+
+    let code = normalize(parts.next()?);
+    if code.is_empty() || !code.chars().all(|c| c.is_alphanumeric() || c == '-') {
+        return None;
+    }
+
+    let discount = parts.next()?;
+    let (percent_off, amount_off) = match discount.strip_suffix('%') {
+        Some(percent) => {
+            let percent: i32 = percent.trim().parse().ok()?;
+            if !(1..=100).contains(&percent) {
+                return None;
+            }
+            (Some(percent), None)
+        }
+        None => {
+            let amount: i32 = discount.parse().ok()?;
+            if amount <= 0 {
+                return None;
+            }
+            (None, Some(amount))
+        }
+    };
+
+    let (valid_from, valid_until) = match optional(parts.next()) {
+        Some(period) => {
+            let (from, until) = period.split_once('-')?;
+            let from = parse_date(from)?;
+            let until = parse_date(until)?;
+            if until < from {
+                return None;
+            }
+            // Последний день периода включительно
+            (
+                Some(PrimitiveDateTime::new(from, Time::MIDNIGHT)),
+                Some(PrimitiveDateTime::new(until.next_day()?, Time::MIDNIGHT)),
+            )
+        }
+        None => (None, None),
+    };
+
+    let max_uses = match optional(parts.next()) {
+        Some(limit) => Some(limit.parse::<i32>().ok().filter(|l| *l > 0)?),
+        None => None,
+    };
+
+    let service_ids = match optional(parts.next()) {
+        Some(ids) => Some(
+            ids.split(',')
+                .map(|id| id.trim().parse::<i32>().ok())
+                .collect::<Option<Vec<i32>>>()?,
+        ),
+        None => None,
+    };
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(NewPromoCode { code, percent_off, amount_off, valid_from, valid_until, max_uses, service_ids })
+}
+
+pub async fn add(pool: &PgPool, promo: NewPromoCode) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO promo_codes (code, percent_off, amount_off, valid_from, valid_until, max_uses, service_ids)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(promo.code)
+    .bind(promo.percent_off)
+    .bind(promo.amount_off)
+    .bind(promo.valid_from)
+    .bind(promo.valid_until)
+    .bind(promo.max_uses)
+    .bind(promo.service_ids)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn toggle(pool: &PgPool, promo_code_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE promo_codes SET is_active = NOT is_active WHERE id = $1")
+        .bind(promo_code_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::{collections::HashMap, error::Error};
+
+use log::error;
+use sqlx::PgPool;
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup}};
+
+use crate::models::{BookingStatus, TelegramId};
+use crate::session::{UserSession, UserStep};
+
+// Отзывы клиентов о завершённых съёмках: оценка от 1 до 5 и необязательный текст.
+// На одну запись — один отзыв, повторная оценка его заменяет
+
+// Сколько последних отзывов показывать в карточке фотографа
+const RECENT_REVIEWS: i64 = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Rating {
+    pub average: f64,
+    pub count: i64,
+}
+
+impl Rating {
+    pub fn label(&self) -> String {
+        format!("⭐ {:.1}", self.average)
+    }
+}
+
+/// Кнопки оценки, отправляются клиенту после завершения записи.
+pub fn rating_keyboard(booking_id: i32) -> InlineKeyboardMarkup {
+    let stars = (1..=5)
+        .map(|n| InlineKeyboardButton::callback(format!("{}⭐", n), format!("rate:{}:{}", booking_id, n)))
+        .collect::<Vec<_>>();
+    InlineKeyboardMarkup::new(vec![stars])
+}
+
+/// Средние оценки всех фотографов, у которых есть отзывы.
+pub async fn ratings(pool: &PgPool) -> Result<HashMap<i32, Rating>, sqlx::Error> {
+    let rows = sqlx::query!(
+        r#"SELECT photographer_id, AVG(rating)::FLOAT8 as "average!", COUNT(*) as "count!"
+         FROM reviews
+         GROUP BY photographer_id"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.photographer_id, Rating { average: row.average, count: row.count }))
+        .collect())
+}
+
+/// Оценка и последние отзывы для карточки фотографа.
+pub async fn photographer_summary(pool: &PgPool, photographer_id: i32) -> Result<String, sqlx::Error> {
+    let Some(rating) = ratings(pool).await?.remove(&photographer_id) else {
+        return Ok("Отзывов пока нет".to_string());
+    };
+
+    let mut text = format!("Рейтинг: {} (отзывов: {})", rating.label(), rating.count);
+
+    let recent = sqlx::query!(
+        r#"SELECT r.rating, r.comment as "comment!", c.name as client_name
+         FROM reviews r
+         JOIN clients c ON r.client_id = c.id
+         WHERE r.photographer_id = $1 AND r.comment IS NOT NULL AND r.comment <> ''
+         ORDER BY r.created_at DESC
+         LIMIT $2"#,
+        photographer_id,
+        RECENT_REVIEWS
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for review in recent {
+        text.push_str(&format!("\n\n{} {}: «{}»", "⭐".repeat(review.rating as usize), review.client_name, review.comment));
+    }
+    Ok(text)
+}
+
+/// Кнопки `rate:{booking_id}:{rating}` и `review_skip:{booking_id}`.
+pub async fn handle_callback(bot: &Bot, chat_id: ChatId, data: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let parts: Vec<&str> = data.split(':').collect();
+    match parts.as_slice() {
+        ["rate", id, rating] => {
+            let booking_id: i32 = id.parse()?;
+            let rating: i32 = rating.parse()?;
+            if !(1..=5).contains(&rating) {
+                return Ok(());
+            }
+
+            let booking = sqlx::query!(
+                r#"SELECT photographer_id as "photographer_id!", status as "status: BookingStatus"
+                 FROM bookings WHERE id = $1 AND client_id = $2"#,
+                booking_id,
+                session.client_id
+            )
+            .fetch_optional(pool)
+            .await?;
+            let Some(booking) = booking.filter(|b| b.status == BookingStatus::Completed) else {
+                bot.send_message(chat_id, "Оценить можно только завершённую съёмку").await?;
+                return Ok(());
+            };
+
+            sqlx::query!(
+                "INSERT INTO reviews (booking_id, client_id, photographer_id, rating)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (booking_id) DO UPDATE SET rating = $4, created_at = CURRENT_TIMESTAMP",
+                booking_id,
+                session.client_id,
+                booking.photographer_id,
+                rating
+            )
+            .execute(pool)
+            .await?;
+
+            session.step = UserStep::ReviewComment;
+            session.edit_target_id = Some(booking_id);
+            let keyboard = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("Пропустить", format!("review_skip:{}", booking_id)),
+            ]]);
+            bot.send_message(chat_id, "Спасибо за оценку! Напишите пару слов о съёмке — отзыв увидят другие клиенты.")
+                .reply_markup(keyboard)
+                .await?;
+        }
+        ["review_skip", _] => {
+            session.step = UserStep::MainMenu;
+            session.edit_target_id = None;
+            bot.send_message(chat_id, "Спасибо, что выбрали нашу студию! 📸").await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Текст отзыва на шаге `ReviewComment`.
+pub async fn handle_comment(bot: &Bot, chat_id: ChatId, text: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    session.step = UserStep::MainMenu;
+    let Some(booking_id) = session.edit_target_id.take() else {
+        return Ok(());
+    };
+
+    let text = text.trim();
+    if text.is_empty() {
+        bot.send_message(chat_id, "Спасибо, что выбрали нашу студию! 📸").await?;
+        return Ok(());
+    }
+
+    let photographer = sqlx::query!(
+        r#"UPDATE reviews r SET comment = $3
+         FROM photographers p
+         WHERE r.booking_id = $1 AND r.client_id = $2 AND p.id = r.photographer_id
+         RETURNING p.telegram_id as "telegram_id: TelegramId", r.rating"#,
+        booking_id,
+        session.client_id,
+        text
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    bot.send_message(chat_id, "Спасибо за отзыв! 💛").await?;
+
+    if let Some(photographer) = photographer {
+        if let Some(telegram_id) = photographer.telegram_id {
+            let message = format!(
+                "Новый отзыв по записи №{}: {}\n«{}»",
+                booking_id,
+                "⭐".repeat(photographer.rating as usize),
+                text
+            );
+            if let Err(e) = bot.send_message(ChatId::from(telegram_id), message).await {
+                error!("Failed to notify photographer about review: {}", e);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use sqlx::{FromRow, PgPool};
+use time::{macros::format_description, Date, Time};
+
+// Повторяющийся график фотографа. Часы по конкретным датам считает
+// функция resolved_working_hours в БД, здесь только хранение и разбор ввода
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateKind {
+    // Рабочие дни недели, 1 = понедельник ... 7 = воскресенье
+    Weekly { weekdays: Vec<i32> },
+    // days_on рабочих дней, затем days_off выходных начиная с anchor
+    Rotation { days_on: i32, days_off: i32, anchor: Date },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleTemplate {
+    pub kind: TemplateKind,
+    pub start_time: Time,
+    pub end_time: Time,
+}
+
+const WEEKDAYS: [&str; 7] = ["пн", "вт", "ср", "чт", "пт", "сб", "вс"];
+
+impl ScheduleTemplate {
+    pub fn describe(&self) -> String {
+        let hours = format!(
+            "{}:{:02}-{}:{:02}",
+            self.start_time.hour(), self.start_time.minute(),
+            self.end_time.hour(), self.end_time.minute()
+        );
+        match &self.kind {
+            TemplateKind::Weekly { weekdays } => {
+                format!("по дням недели: {}, {}", weekdays_label(weekdays), hours)
+            }
+            TemplateKind::Rotation { days_on, days_off, anchor } => {
+                let format = format_description!("[day].[month].[year]");
+                format!(
+                    "{}/{} начиная с {}, {}",
+                    days_on, days_off, anchor.format(&format).unwrap(), hours
+                )
+            }
+        }
+    }
+}
+
+// "Пн, Ср, Пт"
+pub fn weekdays_label(weekdays: &[i32]) -> String {
+    let days: Vec<String> = weekdays
+        .iter()
+        .filter_map(|d| WEEKDAYS.get((*d - 1) as usize))
+        .map(|d| capitalize(d))
+        .collect();
+    days.join(", ")
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// Разбирает интервал "ЧЧ:ММ-ЧЧ:ММ"
+pub fn parse_hours_range(text: &str) -> Option<(Time, Time)> {
+    let (start, end) = text.trim().split_once('-')?;
+    let parse = |value: &str| -> Option<Time> {
+        let (hour, minute) = value.trim().split_once(':')?;
+        Time::from_hms(hour.parse().ok()?, minute.parse().ok()?, 0).ok()
+    };
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start < end { Some((start, end)) } else { None }
+}
+
+fn parse_weekday(value: &str) -> Option<i32> {
+    let value = value.trim().to_lowercase();
+    WEEKDAYS.iter().position(|d| *d == value).map(|i| i as i32 + 1)
+}
+
+// "пн-пт", "пн,ср,пт", "пн-ср,сб"
+pub fn parse_weekdays(text: &str) -> Option<Vec<i32>> {
+    let mut weekdays = Vec::new();
+    for part in text.split(',') {
+        match part.split_once('-') {
+            Some((from, to)) => {
+                let (from, to) = (parse_weekday(from)?, parse_weekday(to)?);
+                if from > to {
+                    return None;
+                }
+                weekdays.extend(from..=to);
+            }
+            None => weekdays.push(parse_weekday(part)?),
+        }
+    }
+    weekdays.sort();
+    weekdays.dedup();
+    if weekdays.is_empty() { None } else { Some(weekdays) }
+}
+
+/// Разбирает шаблон, введённый фотографом:
+/// - `Пн-Пт 9:00-18:00`, `Пн,Ср,Пт 10:00-20:00` — по дням недели;
+/// - `2/2 10:00-20:00` — смены, отсчёт с `today`;
+/// - `2/2 с 01.06.2025 10:00-20:00` — смены с указанной даты.
+pub fn parse_template(text: &str, today: Date) -> Option<ScheduleTemplate> {
+    let (pattern, hours) = text.trim().rsplit_once(' ')?;
+    let (start_time, end_time) = parse_hours_range(hours)?;
+    let pattern = pattern.trim();
+
+    let kind = if let Some((days_on, rest)) = pattern.split_once('/') {
+        let (days_off, anchor) = match rest.split_once(' ') {
+            Some((days_off, anchor)) => {
+                let anchor = anchor.trim().trim_start_matches('с').trim();
+                let format = format_description!("[day].[month].[year]");
+                (days_off, Date::parse(anchor, &format).ok()?)
+            }
+            None => (rest, today),
+        };
+        let days_on: i32 = days_on.trim().parse().ok()?;
+        let days_off: i32 = days_off.trim().parse().ok()?;
+        if days_on <= 0 || days_off < 0 {
+            return None;
+        }
+        TemplateKind::Rotation { days_on, days_off, anchor }
+    } else {
+        TemplateKind::Weekly { weekdays: parse_weekdays(&pattern.replace(' ', ""))? }
+    };
+
+    Some(ScheduleTemplate { kind, start_time, end_time })
+}
+
+#[derive(FromRow)]
+struct TemplateRow {
+    kind: String,
+    weekdays: Option<Vec<i32>>,
+    days_on: Option<i32>,
+    days_off: Option<i32>,
+    anchor_date: Option<Date>,
+    start_time: Time,
+    end_time: Time,
+}
+
+pub async fn get_template(pool: &PgPool, photographer_id: i32) -> Result<Option<ScheduleTemplate>, sqlx::Error> {
+    let row = sqlx::query_as::<_, TemplateRow>(
+        "SELECT kind, weekdays, days_on, days_off, anchor_date, start_time, end_time
+         FROM schedule_templates WHERE photographer_id = $1"
+    )
+    .bind(photographer_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.and_then(|row| {
+        let kind = match row.kind.as_str() {
+            "weekly" => TemplateKind::Weekly { weekdays: row.weekdays? },
+            "rotation" => TemplateKind::Rotation {
+                days_on: row.days_on?,
+                days_off: row.days_off?,
+                anchor: row.anchor_date?,
+            },
+            _ => return None,
+        };
+        Some(ScheduleTemplate { kind, start_time: row.start_time, end_time: row.end_time })
+    }))
+}
+
+pub async fn save_template(pool: &PgPool, photographer_id: i32, template: &ScheduleTemplate) -> Result<(), sqlx::Error> {
+    let (kind, weekdays, days_on, days_off, anchor) = match &template.kind {
+        TemplateKind::Weekly { weekdays } => ("weekly", Some(weekdays.clone()), None, None, None),
+        TemplateKind::Rotation { days_on, days_off, anchor } => ("rotation", None, Some(*days_on), Some(*days_off), Some(*anchor)),
+    };
+
+    sqlx::query(
+        "INSERT INTO schedule_templates (photographer_id, kind, weekdays, days_on, days_off, anchor_date, start_time, end_time)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         ON CONFLICT (photographer_id) DO UPDATE
+         SET kind = $2, weekdays = $3, days_on = $4, days_off = $5, anchor_date = $6, start_time = $7, end_time = $8"
+    )
+    .bind(photographer_id)
+    .bind(kind)
+    .bind(weekdays)
+    .bind(days_on)
+    .bind(days_off)
+    .bind(anchor)
+    .bind(template.start_time)
+    .bind(template.end_time)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_template(pool: &PgPool, photographer_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM schedule_templates WHERE photographer_id = $1")
+        .bind(photographer_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, time};
+
+    use super::*;
+
+    #[test]
+    fn parses_weekday_templates() {
+        let template = parse_template("Пн-Пт 9:00-18:00", date!(2025 - 06 - 02)).unwrap();
+        assert_eq!(template.kind, TemplateKind::Weekly { weekdays: vec![1, 2, 3, 4, 5] });
+        assert_eq!((template.start_time, template.end_time), (time!(9:00), time!(18:00)));
+
+        let template = parse_template("Пн, Ср, Пт 10:00-20:00", date!(2025 - 06 - 02)).unwrap();
+        assert_eq!(template.kind, TemplateKind::Weekly { weekdays: vec![1, 3, 5] });
+
+        let template = parse_template("пн-ср,сб,вт 10:00-20:00", date!(2025 - 06 - 02)).unwrap();
+        assert_eq!(template.kind, TemplateKind::Weekly { weekdays: vec![1, 2, 3, 6] });
+    }
+
+    #[test]
+    fn parses_rotation_templates() {
+        let template = parse_template("2/2 10:00-20:00", date!(2025 - 06 - 02)).unwrap();
+        assert_eq!(template.kind, TemplateKind::Rotation { days_on: 2, days_off: 2, anchor: date!(2025 - 06 - 02) });
+
+        let template = parse_template("5/2 с 01.06.2025 9:30-18:00", date!(2025 - 06 - 10)).unwrap();
+        assert_eq!(template.kind, TemplateKind::Rotation { days_on: 5, days_off: 2, anchor: date!(2025 - 06 - 01) });
+        assert_eq!((template.start_time, template.end_time), (time!(9:30), time!(18:00)));
+    }
+
+    #[test]
+    fn rejects_invalid_templates() {
+        let today = date!(2025 - 06 - 02);
+        assert_eq!(parse_template("Пт-Пн 9:00-18:00", today), None);
+        assert_eq!(parse_template("Пн-Пт", today), None);
+        assert_eq!(parse_template("Будни 9:00-18:00", today), None);
+        assert_eq!(parse_template("0/2 10:00-20:00", today), None);
+        assert_eq!(parse_template("2/-1 10:00-20:00", today), None);
+        assert_eq!(parse_template("2/2 с 31.02.2025 10:00-20:00", today), None);
+    }
+
+    #[test]
+    fn parses_hours_ranges() {
+        assert_eq!(parse_hours_range("9:00-18:00"), Some((time!(9:00), time!(18:00))));
+        assert_eq!(parse_hours_range(" 09:30 - 13:15 "), Some((time!(9:30), time!(13:15))));
+    }
+
+    #[test]
+    fn rejects_invalid_hours_ranges() {
+        assert_eq!(parse_hours_range("18:00-9:00"), None);
+        assert_eq!(parse_hours_range("9:00-9:00"), None);
+        assert_eq!(parse_hours_range("9-18"), None);
+        assert_eq!(parse_hours_range("9:00-24:00"), None);
+        assert_eq!(parse_hours_range("9:00-18:60"), None);
+        assert_eq!(parse_hours_range("9:00"), None);
+    }
+}
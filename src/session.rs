@@ -0,0 +1,460 @@
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use sqlx::{FromRow, PgPool};
+use time::{Date, Time};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+// Структура для хранения сессии пользователя
+#[derive(Clone)]
+pub struct UserSession {
+    pub step: UserStep,
+    pub client_id: i32,
+    pub photographer_id: Option<i32>,
+    pub service_id: Option<i32>,
+    pub selected_date: Option<Date>,
+    pub selected_time_start: Option<Time>,
+    pub selected_time_end: Option<Time>,
+    pub agreement: bool,
+    pub user_type: UserType,
+    pub client_name: String,
+    pub client_username: String,
+    // Запись, которую клиент сейчас переносит
+    pub reschedule_booking_id: Option<i32>,
+    // Фотограф или услуга, которую сейчас редактируют
+    pub edit_target_id: Option<i32>,
+    // Промокод, применённый к оформляемой записи
+    pub promo_code_id: Option<i32>,
+}
+
+impl UserSession {
+    pub fn new() -> Self {
+        UserSession {
+            step: UserStep::Registartion,
+            client_id: -1,
+            photographer_id: None,
+            service_id: None,
+            selected_date: None,
+            selected_time_start: None,
+            selected_time_end: None,
+            agreement: false,
+            user_type: UserType::Unknown,
+            client_name: String::new(),
+            client_username: String::new(),
+            reschedule_booking_id: None,
+            edit_target_id: None,
+            promo_code_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UserType {
+    Unknown,
+    Client,
+    Photographer,
+    Admin
+}
+
+impl UserType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            UserType::Unknown => "unknown",
+            UserType::Client => "client",
+            UserType::Photographer => "photographer",
+            UserType::Admin => "admin",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "unknown" => Some(UserType::Unknown),
+            "client" => Some(UserType::Client),
+            "photographer" => Some(UserType::Photographer),
+            "admin" => Some(UserType::Admin),
+            _ => None,
+        }
+    }
+}
+
+// Перечисление шагов процесса
+#[derive(Debug, Clone, Copy)]
+pub enum UserStep {
+    Registartion,
+    Start,
+    MainMenu,
+    HistoryOfBookings,
+    SelectingService,
+    SelectingPhotographer,
+    SelectingTime,
+    ConfirmingBooking,
+    Payment,
+    ReviewComment,
+    // Photographer specific steps
+    PhotographerMainMenu,
+    ViewSchedule,
+    ViewBookings,
+    ChangeDescription,
+    ChangePortfolio,
+    CustomHours,
+    EditScheduleTemplate,
+    MyServicePrice,
+    MyServiceDuration,
+    UploadMaterials,
+    // Admin specific steps
+    AdminMenu,
+    AdminAddPhotographer,
+    AdminEditPhotographer,
+    AdminFindBooking,
+    AdminAddPriceRule,
+    AdminAddPromoCode,
+    // Service catalogue steps (admin or authorised photographer)
+    CatalogAddService,
+    CatalogEditName,
+    CatalogEditPrice,
+    CatalogEditDuration,
+    CatalogEditComment,
+    CatalogEditBuffers,
+    // New steps
+    ChangeName,
+    PersonalCabinet,
+    SelectTime,
+}
+
+impl UserStep {
+    fn as_str(&self) -> &'static str {
+        match self {
+            UserStep::Registartion => "registration",
+            UserStep::Start => "start",
+            UserStep::MainMenu => "main_menu",
+            UserStep::HistoryOfBookings => "history_of_bookings",
+            UserStep::SelectingService => "selecting_service",
+            UserStep::SelectingPhotographer => "selecting_photographer",
+            UserStep::SelectingTime => "selecting_time",
+            UserStep::ConfirmingBooking => "confirming_booking",
+            UserStep::Payment => "payment",
+            UserStep::ReviewComment => "review_comment",
+            UserStep::PhotographerMainMenu => "photographer_main_menu",
+            UserStep::ViewSchedule => "view_schedule",
+            UserStep::ViewBookings => "view_bookings",
+            UserStep::ChangeDescription => "change_description",
+            UserStep::ChangePortfolio => "change_portfolio",
+            UserStep::CustomHours => "custom_hours",
+            UserStep::EditScheduleTemplate => "edit_schedule_template",
+            UserStep::MyServicePrice => "my_service_price",
+            UserStep::MyServiceDuration => "my_service_duration",
+            UserStep::UploadMaterials => "upload_materials",
+            UserStep::AdminMenu => "admin_menu",
+            UserStep::AdminAddPhotographer => "admin_add_photographer",
+            UserStep::AdminEditPhotographer => "admin_edit_photographer",
+            UserStep::AdminFindBooking => "admin_find_booking",
+            UserStep::AdminAddPriceRule => "admin_add_price_rule",
+            UserStep::AdminAddPromoCode => "admin_add_promo_code",
+            UserStep::CatalogAddService => "catalog_add_service",
+            UserStep::CatalogEditName => "catalog_edit_name",
+            UserStep::CatalogEditPrice => "catalog_edit_price",
+            UserStep::CatalogEditDuration => "catalog_edit_duration",
+            UserStep::CatalogEditComment => "catalog_edit_comment",
+            UserStep::CatalogEditBuffers => "catalog_edit_buffers",
+            UserStep::ChangeName => "change_name",
+            UserStep::PersonalCabinet => "personal_cabinet",
+            UserStep::SelectTime => "select_time",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "registration" => Some(UserStep::Registartion),
+            "start" => Some(UserStep::Start),
+            "main_menu" => Some(UserStep::MainMenu),
+            "history_of_bookings" => Some(UserStep::HistoryOfBookings),
+            "selecting_service" => Some(UserStep::SelectingService),
+            "selecting_photographer" => Some(UserStep::SelectingPhotographer),
+            "selecting_time" => Some(UserStep::SelectingTime),
+            "confirming_booking" => Some(UserStep::ConfirmingBooking),
+            "payment" => Some(UserStep::Payment),
+            "review_comment" => Some(UserStep::ReviewComment),
+            "photographer_main_menu" => Some(UserStep::PhotographerMainMenu),
+            "view_schedule" => Some(UserStep::ViewSchedule),
+            "view_bookings" => Some(UserStep::ViewBookings),
+            "change_description" => Some(UserStep::ChangeDescription),
+            "change_portfolio" => Some(UserStep::ChangePortfolio),
+            "custom_hours" => Some(UserStep::CustomHours),
+            "edit_schedule_template" => Some(UserStep::EditScheduleTemplate),
+            "my_service_price" => Some(UserStep::MyServicePrice),
+            "my_service_duration" => Some(UserStep::MyServiceDuration),
+            "upload_materials" => Some(UserStep::UploadMaterials),
+            "admin_menu" => Some(UserStep::AdminMenu),
+            "admin_add_photographer" => Some(UserStep::AdminAddPhotographer),
+            "admin_edit_photographer" => Some(UserStep::AdminEditPhotographer),
+            "admin_find_booking" => Some(UserStep::AdminFindBooking),
+            "admin_add_price_rule" => Some(UserStep::AdminAddPriceRule),
+            "admin_add_promo_code" => Some(UserStep::AdminAddPromoCode),
+            "catalog_add_service" => Some(UserStep::CatalogAddService),
+            "catalog_edit_name" => Some(UserStep::CatalogEditName),
+            "catalog_edit_price" => Some(UserStep::CatalogEditPrice),
+            "catalog_edit_duration" => Some(UserStep::CatalogEditDuration),
+            "catalog_edit_comment" => Some(UserStep::CatalogEditComment),
+            "catalog_edit_buffers" => Some(UserStep::CatalogEditBuffers),
+            "change_name" => Some(UserStep::ChangeName),
+            "personal_cabinet" => Some(UserStep::PersonalCabinet),
+            "select_time" => Some(UserStep::SelectTime),
+            _ => None,
+        }
+    }
+}
+
+/// Хранилище пользовательских сессий.
+///
+/// Сессия загружается перед обработкой каждого обновления и сохраняется
+/// после него, поэтому незавершённая запись переживает перезапуск бота.
+pub trait SessionStore: Send + Sync + 'static {
+    /// Возвращает сессию чата или новую, если сохранённой нет.
+    fn load(&self, chat_id: i64) -> impl Future<Output = Result<UserSession, sqlx::Error>> + Send;
+
+    fn save(&self, chat_id: i64, session: &UserSession) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+}
+
+#[derive(FromRow)]
+struct SessionRow {
+    step: String,
+    user_type: String,
+    client_id: i32,
+    photographer_id: Option<i32>,
+    service_id: Option<i32>,
+    selected_date: Option<Date>,
+    selected_time_start: Option<Time>,
+    selected_time_end: Option<Time>,
+    agreement: bool,
+    client_name: String,
+    client_username: String,
+    reschedule_booking_id: Option<i32>,
+    edit_target_id: Option<i32>,
+    promo_code_id: Option<i32>,
+}
+
+impl From<SessionRow> for UserSession {
+    fn from(row: SessionRow) -> Self {
+        UserSession {
+            // Неизвестный шаг (например, после переименования) возвращает пользователя к началу
+            step: UserStep::parse(&row.step).unwrap_or(UserStep::Start),
+            client_id: row.client_id,
+            photographer_id: row.photographer_id,
+            service_id: row.service_id,
+            selected_date: row.selected_date,
+            selected_time_start: row.selected_time_start,
+            selected_time_end: row.selected_time_end,
+            agreement: row.agreement,
+            user_type: UserType::parse(&row.user_type).unwrap_or(UserType::Unknown),
+            client_name: row.client_name,
+            client_username: row.client_username,
+            reschedule_booking_id: row.reschedule_booking_id,
+            edit_target_id: row.edit_target_id,
+            promo_code_id: row.promo_code_id,
+        }
+    }
+}
+
+/// Сессии в таблице `user_sessions`.
+pub struct PgSessionStore {
+    pool: PgPool,
+}
+
+impl PgSessionStore {
+    pub fn new(pool: PgPool) -> Self {
+        PgSessionStore { pool }
+    }
+}
+
+impl SessionStore for PgSessionStore {
+    async fn load(&self, chat_id: i64) -> Result<UserSession, sqlx::Error> {
+        let row = sqlx::query_as::<_, SessionRow>(
+            "SELECT step, user_type, client_id, photographer_id, service_id,
+                    selected_date, selected_time_start, selected_time_end,
+                    agreement, client_name, client_username, reschedule_booking_id, edit_target_id,
+                    promo_code_id
+             FROM user_sessions WHERE chat_id = $1"
+        )
+        .bind(chat_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(UserSession::from).unwrap_or_else(UserSession::new))
+    }
+
+    async fn save(&self, chat_id: i64, session: &UserSession) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO user_sessions (chat_id, step, user_type, client_id, photographer_id, service_id,
+                                        selected_date, selected_time_start, selected_time_end,
+                                        agreement, client_name, client_username, reschedule_booking_id, edit_target_id,
+                                        promo_code_id, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, CURRENT_TIMESTAMP)
+             ON CONFLICT (chat_id) DO UPDATE SET
+                step = $2, user_type = $3, client_id = $4, photographer_id = $5, service_id = $6,
+                selected_date = $7, selected_time_start = $8, selected_time_end = $9,
+                agreement = $10, client_name = $11, client_username = $12,
+                reschedule_booking_id = $13, edit_target_id = $14,
+                promo_code_id = $15, updated_at = CURRENT_TIMESTAMP"
+        )
+        .bind(chat_id)
+        .bind(session.step.as_str())
+        .bind(session.user_type.as_str())
+        .bind(session.client_id)
+        .bind(session.photographer_id)
+        .bind(session.service_id)
+        .bind(session.selected_date)
+        .bind(session.selected_time_start)
+        .bind(session.selected_time_end)
+        .bind(session.agreement)
+        .bind(&session.client_name)
+        .bind(&session.client_username)
+        .bind(session.reschedule_booking_id)
+        .bind(session.edit_target_id)
+        .bind(session.promo_code_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Сессии в памяти процесса: для тестов и локального запуска без таблицы сессий.
+pub struct InMemorySessionStore {
+    sessions: Mutex<HashMap<i64, UserSession>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        InMemorySessionStore { sessions: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl SessionStore for InMemorySessionStore {
+    async fn load(&self, chat_id: i64) -> Result<UserSession, sqlx::Error> {
+        let sessions = self.sessions.lock().await;
+        Ok(sessions.get(&chat_id).cloned().unwrap_or_else(UserSession::new))
+    }
+
+    async fn save(&self, chat_id: i64, session: &UserSession) -> Result<(), sqlx::Error> {
+        self.sessions.lock().await.insert(chat_id, session.clone());
+        Ok(())
+    }
+}
+
+/// Блокировки по чатам.
+///
+/// Обновления одного чата обрабатываются строго по очереди (иначе две
+/// параллельные загрузки сессии затрут изменения друг друга), а разные
+/// чаты не ждут друг друга, даже если один из них упёрся в медленный запрос.
+#[derive(Default)]
+pub struct ChatLocks {
+    locks: std::sync::Mutex<HashMap<i64, Arc<Mutex<()>>>>,
+}
+
+impl ChatLocks {
+    pub fn new() -> Self {
+        ChatLocks::default()
+    }
+
+    pub async fn lock(&self, chat_id: i64) -> ChatGuard<'_> {
+        let lock = self.locks.lock().unwrap().entry(chat_id).or_default().clone();
+        let guard = lock.lock_owned().await;
+        ChatGuard { locks: self, chat_id, guard: Some(guard) }
+    }
+}
+
+pub struct ChatGuard<'a> {
+    locks: &'a ChatLocks,
+    chat_id: i64,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for ChatGuard<'_> {
+    fn drop(&mut self) {
+        self.guard.take();
+        // Убираем запись, если этот чат больше никто не ждёт, чтобы карта не росла бесконечно
+        let mut locks = self.locks.locks.lock().unwrap();
+        if locks.get(&self.chat_id).is_some_and(|lock| Arc::strong_count(lock) == 1) {
+            locks.remove(&self.chat_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, time};
+
+    use super::*;
+
+    fn booking_in_progress() -> UserSession {
+        let mut session = UserSession::new();
+        session.step = UserStep::ConfirmingBooking;
+        session.user_type = UserType::Client;
+        session.client_id = 42;
+        session.photographer_id = Some(3);
+        session.service_id = Some(2);
+        session.selected_date = Some(date!(2025 - 06 - 02));
+        session.selected_time_start = Some(time!(10:00));
+        session.selected_time_end = Some(time!(11:30));
+        session.agreement = true;
+        session.client_name = "Вика".to_string();
+        session.client_username = "vika".to_string();
+        session
+    }
+
+    fn assert_same(loaded: &UserSession, expected: &UserSession) {
+        assert_eq!(loaded.step.as_str(), expected.step.as_str());
+        assert_eq!(loaded.user_type, expected.user_type);
+        assert_eq!(loaded.client_id, expected.client_id);
+        assert_eq!(loaded.photographer_id, expected.photographer_id);
+        assert_eq!(loaded.service_id, expected.service_id);
+        assert_eq!(loaded.selected_date, expected.selected_date);
+        assert_eq!(loaded.selected_time_start, expected.selected_time_start);
+        assert_eq!(loaded.selected_time_end, expected.selected_time_end);
+        assert_eq!(loaded.agreement, expected.agreement);
+        assert_eq!(loaded.client_name, expected.client_name);
+        assert_eq!(loaded.client_username, expected.client_username);
+    }
+
+    async fn round_trip(store: &impl SessionStore) {
+        assert_same(&store.load(1).await.unwrap(), &UserSession::new());
+
+        let mut session = booking_in_progress();
+        store.save(1, &session).await.unwrap();
+        assert_same(&store.load(1).await.unwrap(), &session);
+
+        session.step = UserStep::MainMenu;
+        session.service_id = None;
+        session.selected_date = None;
+        store.save(1, &session).await.unwrap();
+        assert_same(&store.load(1).await.unwrap(), &session);
+
+        // Сессии разных чатов не смешиваются
+        assert_same(&store.load(2).await.unwrap(), &UserSession::new());
+    }
+
+    #[tokio::test]
+    async fn in_memory_store_round_trip() {
+        round_trip(&InMemorySessionStore::new()).await;
+    }
+
+    #[sqlx::test]
+    async fn pg_store_round_trip(pool: PgPool) {
+        round_trip(&PgSessionStore::new(pool)).await;
+    }
+
+    #[tokio::test]
+    async fn updates_of_one_chat_are_serialized() {
+        let locks = ChatLocks::new();
+        let wait = std::time::Duration::from_millis(50);
+
+        let guard = locks.lock(1).await;
+        // Второе обновление того же чата ждёт, пока первое не закончится
+        assert!(tokio::time::timeout(wait, locks.lock(1)).await.is_err());
+        // Другие чаты не ждут
+        assert!(tokio::time::timeout(wait, locks.lock(2)).await.is_ok());
+
+        drop(guard);
+        assert!(tokio::time::timeout(wait, locks.lock(1)).await.is_ok());
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+}
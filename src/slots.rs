@@ -0,0 +1,146 @@
+use time::Time;
+
+// Интервал внутри одного дня в минутах от полуночи, конец не включается
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Interval {
+    pub fn new(start: i32, end: i32) -> Self {
+        Interval { start, end }
+    }
+
+    pub fn from_times(start: Time, end: Time) -> Self {
+        Interval::new(minutes_of(start), minutes_of(end))
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+
+    pub fn contains(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    // Слот в формате "ЧЧ:ММ-ЧЧ:ММ", который используется в callback "time-..."
+    pub fn label(&self) -> String {
+        format!(
+            "{:02}:{:02}-{:02}:{:02}",
+            self.start / 60, self.start % 60,
+            self.end / 60, self.end % 60
+        )
+    }
+}
+
+pub fn minutes_of(time: Time) -> i32 {
+    time.hour() as i32 * 60 + time.minute() as i32
+}
+
+// Параметры услуги для расчёта слотов
+pub struct SlotRequest {
+    pub duration: i32,
+    // Подготовка студии перед съёмкой и уборка после неё
+    pub buffer_before: i32,
+    pub buffer_after: i32,
+    pub step: i32,
+}
+
+impl SlotRequest {
+    // Время, которое слот занимает вместе с буферами
+    pub fn occupied(&self, slot: &Interval) -> Interval {
+        Interval::new(slot.start - self.buffer_before, slot.end + self.buffer_after)
+    }
+}
+
+/// Свободные слоты в пределах рабочего окна.
+///
+/// Слоты начинаются на сетке с шагом `step` от полуночи. Слот подходит, если
+/// вместе с буферами он помещается в рабочее окно и не пересекается ни с
+/// одним занятым интервалом фотографа (занятые интервалы уже включают буферы
+/// своих услуг). Если услуге нужен зал, `rooms` содержит занятость каждого
+/// подходящего зала, и хотя бы один из них должен быть свободен.
+pub fn free_slots(work: Interval, busy: &[Interval], rooms: Option<&[Vec<Interval>]>, request: &SlotRequest) -> Vec<Interval> {
+    let mut slots = Vec::new();
+    if request.duration <= 0 || request.step <= 0 {
+        return slots;
+    }
+
+    let earliest = work.start + request.buffer_before;
+    let mut start = (earliest + request.step - 1).div_euclid(request.step) * request.step;
+
+    while start + request.duration + request.buffer_after <= work.end {
+        let slot = Interval::new(start, start + request.duration);
+        let occupied = request.occupied(&slot);
+        let room_free = match rooms {
+            Some(rooms) => rooms.iter().any(|room| !room.iter().any(|b| b.overlaps(&occupied))),
+            None => true,
+        };
+        if work.contains(&occupied) && !busy.iter().any(|b| b.overlaps(&occupied)) && room_free {
+            slots.push(slot);
+        }
+        start += request.step;
+    }
+
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(duration: i32, buffer_before: i32, buffer_after: i32, step: i32) -> SlotRequest {
+        SlotRequest { duration, buffer_before, buffer_after, step }
+    }
+
+    fn labels(slots: &[Interval]) -> Vec<String> {
+        slots.iter().map(Interval::label).collect()
+    }
+
+    #[test]
+    fn slots_start_on_the_grid() {
+        // Рабочий день с 9:05 — первый слот на ближайшей отметке сетки
+        let slots = free_slots(Interval::new(545, 720), &[], None, &request(60, 0, 0, 30));
+        assert_eq!(labels(&slots), vec!["09:30-10:30", "10:00-11:00", "10:30-11:30", "11:00-12:00"]);
+    }
+
+    #[test]
+    fn buffers_must_fit_into_working_hours() {
+        let slots = free_slots(Interval::new(540, 720), &[], None, &request(60, 15, 15, 30));
+        assert_eq!(labels(&slots), vec!["09:30-10:30", "10:00-11:00", "10:30-11:30"]);
+    }
+
+    #[test]
+    fn buffers_must_not_overlap_busy_time() {
+        let busy = [Interval::new(600, 660)];
+        let without_buffer = free_slots(Interval::new(540, 780), &busy, None, &request(60, 0, 0, 60));
+        assert_eq!(labels(&without_buffer), vec!["09:00-10:00", "11:00-12:00", "12:00-13:00"]);
+
+        // Уборка после съёмки в 9:00 заходит на занятое время
+        let with_buffer = free_slots(Interval::new(540, 780), &busy, None, &request(60, 0, 15, 60));
+        assert_eq!(labels(&with_buffer), vec!["11:00-12:00"]);
+    }
+
+    #[test]
+    fn slot_needs_at_least_one_free_room() {
+        let rooms = vec![
+            vec![Interval::new(540, 600), Interval::new(600, 660)],
+            vec![Interval::new(600, 660)],
+        ];
+        let slots = free_slots(Interval::new(540, 720), &[], Some(rooms.as_slice()), &request(60, 0, 0, 60));
+        assert_eq!(labels(&slots), vec!["09:00-10:00", "11:00-12:00"]);
+    }
+
+    #[test]
+    fn no_slots_without_suitable_rooms() {
+        let slots = free_slots(Interval::new(540, 720), &[], Some(&[][..]), &request(60, 0, 0, 60));
+        assert!(slots.is_empty());
+    }
+
+    #[test]
+    fn invalid_request_gives_no_slots() {
+        assert!(free_slots(Interval::new(540, 720), &[], None, &request(60, 0, 0, 0)).is_empty());
+        assert!(free_slots(Interval::new(540, 720), &[], None, &request(0, 0, 0, 30)).is_empty());
+    }
+}
@@ -0,0 +1,286 @@
+use std::error::Error;
+
+use log::error;
+use sqlx::{PgPool, Postgres, Transaction};
+use teloxide::{prelude::*, types::{InlineKeyboardButton, InlineKeyboardMarkup, Message}};
+use time::{macros::{format_description, time}, Date, PrimitiveDateTime, Time};
+use tokio::sync::Mutex;
+
+use crate::config;
+use crate::handlers::{get_free_slots, show_booking_summary};
+use crate::models::TelegramId;
+use crate::session::{UserSession, UserStep};
+
+// Лист ожидания: клиент встаёт в очередь на дату, когда свободных слотов нет.
+// Когда время освобождается (отмена записи, новые рабочие часы), первый в очереди
+// получает предложение, и слот удерживается за ним waitlist_hold_minutes.
+// Удержанные слоты не показываются другим клиентам
+
+// Очередь разбирается по одному, иначе два вызова могут предложить один слот двоим
+static PROCESS_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Кнопки под сообщением «нет свободных слотов».
+pub fn join_keyboard() -> InlineKeyboardMarkup {
+    InlineKeyboardMarkup::new(vec![
+        vec![InlineKeyboardButton::callback("📝 Встать в лист ожидания", "waitlist:join")],
+        vec![InlineKeyboardButton::callback("⟵ Назад к выбору даты", "back_to_calendar")],
+    ])
+}
+
+/// Удерживается ли время за другим клиентом. Проверяется внутри транзакции записи.
+pub async fn is_held_by_other(
+    tx: &mut Transaction<'_, Postgres>,
+    photographer_id: i32,
+    client_id: i32,
+    booking_start: PrimitiveDateTime,
+    booking_end: PrimitiveDateTime,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS(
+             SELECT 1 FROM waitlist
+             WHERE status = 'offered'
+             AND hold_until > CURRENT_TIMESTAMP
+             AND offered_photographer_id = $1
+             AND client_id <> $2
+             AND offered_start < $4::timestamp
+             AND offered_end > $3::timestamp
+         ) as "held!""#,
+        photographer_id,
+        client_id,
+        booking_start,
+        booking_end
+    )
+    .fetch_one(&mut **tx)
+    .await
+}
+
+/// Отмечает предложение выполненным, когда клиент записался на предложенный слот.
+pub async fn mark_booked(pool: &PgPool, client_id: i32, photographer_id: i32, booking_start: PrimitiveDateTime) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE waitlist SET status = 'booked'
+         WHERE client_id = $1 AND offered_photographer_id = $2 AND offered_start = $3::timestamp AND status = 'offered'",
+        client_id,
+        photographer_id,
+        booking_start
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Кнопки `waitlist:join`, `waitlist:leave:{id}`, `waitlist:accept:{id}`, `waitlist:decline:{id}`.
+pub async fn handle_callback(bot: &Bot, msg: &Message, data: &str, pool: &PgPool, session: &mut UserSession) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let chat_id = msg.chat.id;
+    let parts: Vec<&str> = data.split(':').collect();
+    match parts.as_slice() {
+        ["waitlist", "join"] => {
+            let (Some(service_id), Some(date)) = (session.service_id, session.selected_date) else {
+                return Ok(());
+            };
+            if session.reschedule_booking_id.is_some() {
+                bot.send_message(chat_id, "Лист ожидания доступен только для новой записи. Выберите другую дату для переноса.").await?;
+                return Ok(());
+            }
+            let waitlist_id = sqlx::query_scalar!(
+                "INSERT INTO waitlist (client_id, service_id, photographer_id, date)
+                 SELECT $1, $2, $3, $4
+                 WHERE NOT EXISTS (
+                     SELECT 1 FROM waitlist
+                     WHERE client_id = $1 AND service_id = $2 AND date = $4
+                     AND photographer_id IS NOT DISTINCT FROM $3
+                     AND status IN ('waiting', 'offered')
+                 )
+                 RETURNING id",
+                session.client_id,
+                service_id,
+                session.photographer_id,
+                date
+            )
+            .fetch_optional(pool)
+            .await?;
+
+            let text = match waitlist_id {
+                Some(_) => format!(
+                    "Вы в листе ожидания на {}. Как только появится свободное время, я пришлю предложение — \
+                    на ответ будет {} мин.",
+                    date.format(format_description!("[day].[month].[year]")).unwrap(),
+                    config::get().waitlist_hold_minutes
+                ),
+                None => "Вы уже в листе ожидания на эту дату".to_string(),
+            };
+            let mut keyboard = Vec::new();
+            if let Some(waitlist_id) = waitlist_id {
+                keyboard.push(vec![InlineKeyboardButton::callback("Выйти из листа ожидания", format!("waitlist:leave:{}", waitlist_id))]);
+            }
+            bot.edit_message_text(chat_id, msg.id, text)
+                .reply_markup(InlineKeyboardMarkup::new(keyboard))
+                .await?;
+            session.step = UserStep::MainMenu;
+        }
+        ["waitlist", "leave", id] => {
+            let waitlist_id: i32 = id.parse()?;
+            sqlx::query!(
+                "UPDATE waitlist SET status = 'cancelled'
+                 WHERE id = $1 AND client_id = $2 AND status IN ('waiting', 'offered')",
+                waitlist_id,
+                session.client_id
+            )
+            .execute(pool)
+            .await?;
+            bot.edit_message_text(chat_id, msg.id, "Вы вышли из листа ожидания").await?;
+            // Удержанный слот мог освободиться
+            process(bot, pool).await?;
+        }
+        ["waitlist", "accept", id] => {
+            let waitlist_id: i32 = id.parse()?;
+            let offer = sqlx::query!(
+                r#"SELECT service_id, offered_photographer_id as "photographer_id!",
+                          offered_start::timestamp as "offered_start!", offered_end::timestamp as "offered_end!"
+                 FROM waitlist
+                 WHERE id = $1 AND client_id = $2 AND status = 'offered' AND hold_until > CURRENT_TIMESTAMP"#,
+                waitlist_id,
+                session.client_id
+            )
+            .fetch_optional(pool)
+            .await?;
+            let Some(offer) = offer else {
+                bot.edit_message_text(chat_id, msg.id, "Это предложение уже неактуально").await?;
+                return Ok(());
+            };
+
+            // Дальше обычное подтверждение записи: сводка, промокод, оплата
+            session.service_id = Some(offer.service_id);
+            session.photographer_id = Some(offer.photographer_id);
+            session.selected_date = Some(offer.offered_start.date());
+            session.selected_time_start = Some(offer.offered_start.time());
+            session.selected_time_end = Some(offer.offered_end.time());
+            session.reschedule_booking_id = None;
+            session.promo_code_id = None;
+            session.step = UserStep::SelectingTime;
+            show_booking_summary(bot, chat_id, pool, session, Some(msg.id)).await?;
+        }
+        ["waitlist", "decline", id] => {
+            let waitlist_id: i32 = id.parse()?;
+            sqlx::query!(
+                "UPDATE waitlist SET status = 'declined'
+                 WHERE id = $1 AND client_id = $2 AND status = 'offered'",
+                waitlist_id,
+                session.client_id
+            )
+            .execute(pool)
+            .await?;
+            bot.edit_message_text(chat_id, msg.id, "Хорошо, предложение передано следующему в очереди").await?;
+            process(bot, pool).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Снимает просроченные удержания и предлагает свободные слоты очереди.
+/// Вызывается фоновой задачей и сразу после отмены записи или изменения рабочих часов.
+pub async fn process(bot: &Bot, pool: &PgPool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let _guard = PROCESS_LOCK.lock().await;
+
+    let expired = sqlx::query!(
+        r#"UPDATE waitlist w SET status = 'expired'
+         FROM clients c
+         WHERE w.client_id = c.id AND w.status = 'offered' AND w.hold_until <= CURRENT_TIMESTAMP
+         RETURNING w.id, c.telegram_id as "telegram_id: TelegramId""#
+    )
+    .fetch_all(pool)
+    .await?;
+    for entry in expired {
+        if let Err(e) = bot.send_message(
+            ChatId::from(entry.telegram_id),
+            "Время на ответ по листу ожидания истекло, слот предложен следующему клиенту. Вы можете записаться заново.",
+        ).await {
+            error!("Failed to notify client about expired waitlist offer {}: {}", entry.id, e);
+        }
+    }
+
+    sqlx::query!("UPDATE waitlist SET status = 'expired' WHERE status = 'waiting' AND date < CURRENT_DATE")
+        .execute(pool)
+        .await?;
+
+    let queue = sqlx::query!(
+        r#"SELECT w.id, w.service_id, w.photographer_id, w.date, c.telegram_id as "telegram_id: TelegramId", s.name as service_name
+         FROM waitlist w
+         JOIN clients c ON w.client_id = c.id
+         JOIN services s ON w.service_id = s.id
+         WHERE w.status = 'waiting'
+         ORDER BY w.created_at, w.id"#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for entry in queue {
+        let candidates = match entry.photographer_id {
+            Some(photographer_id) => vec![photographer_id],
+            None => sqlx::query_scalar!(
+                "SELECT p.id FROM photographers p
+                 JOIN photographer_services ps ON p.id = ps.photographer_id
+                 WHERE ps.service_id = $1 AND p.is_active
+                 ORDER BY p.id",
+                entry.service_id
+            )
+            .fetch_all(pool)
+            .await?,
+        };
+
+        let Some((photographer_id, start, end)) = first_free_slot(pool, &candidates, entry.service_id, entry.date).await? else {
+            continue;
+        };
+
+        let offered = sqlx::query!(
+            "UPDATE waitlist
+             SET status = 'offered', offered_photographer_id = $2, offered_start = $3::timestamp, offered_end = $4::timestamp,
+                 hold_until = CURRENT_TIMESTAMP + make_interval(mins => $5)
+             WHERE id = $1 AND status = 'waiting'",
+            entry.id,
+            photographer_id,
+            start,
+            end,
+            config::get().waitlist_hold_minutes
+        )
+        .execute(pool)
+        .await?
+        .rows_affected();
+        if offered == 0 {
+            continue;
+        }
+
+        let keyboard = InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback("✅ Записаться", format!("waitlist:accept:{}", entry.id)),
+            InlineKeyboardButton::callback("Отказаться", format!("waitlist:decline:{}", entry.id)),
+        ]]);
+        let format = format_description!("[day].[month].[year] [hour]:[minute]");
+        let text = format!(
+            "🎉 Освободилось время: {}, {}. Слот удерживается за вами {} мин.",
+            entry.service_name,
+            start.format(&format).unwrap(),
+            config::get().waitlist_hold_minutes
+        );
+        if let Err(e) = bot.send_message(ChatId::from(entry.telegram_id), text).reply_markup(keyboard).await {
+            error!("Failed to send waitlist offer {}: {}", entry.id, e);
+        }
+    }
+
+    Ok(())
+}
+
+// Первый свободный слот у любого из фотографов на дату
+async fn first_free_slot(pool: &PgPool, photographer_ids: &[i32], service_id: i32, date: Date) -> Result<Option<(i32, PrimitiveDateTime, PrimitiveDateTime)>, sqlx::Error> {
+    let format = format_description!("[hour]:[minute]");
+    for photographer_id in photographer_ids {
+        let slots = get_free_slots(pool, *photographer_id, service_id, PrimitiveDateTime::new(date, time!(0:00))).await?;
+        let parsed = slots.first().and_then(|slot| {
+            let (start, end) = slot.split_once('-')?;
+            Some((Time::parse(start, &format).ok()?, Time::parse(end, &format).ok()?))
+        });
+        if let Some((start, end)) = parsed {
+            return Ok(Some((*photographer_id, PrimitiveDateTime::new(date, start), PrimitiveDateTime::new(date, end))));
+        }
+    }
+    Ok(None)
+}